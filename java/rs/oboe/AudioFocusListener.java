@@ -0,0 +1,32 @@
+package rs.oboe;
+
+import android.media.AudioManager;
+
+/**
+ * Forwards the audio focus changes to the handler of the native `AudioFocus`
+ *
+ * The app must include this class into its build to receive the focus changes.
+ */
+public final class AudioFocusListener implements AudioManager.OnAudioFocusChangeListener {
+    private long handle;
+
+    public AudioFocusListener(long handle) {
+        this.handle = handle;
+    }
+
+    @Override
+    public synchronized void onAudioFocusChange(int focusChange) {
+        if (handle != 0) {
+            nativeOnAudioFocusChange(handle, focusChange);
+        }
+    }
+
+    /**
+     * Detach the native handler, it waits for the running change notification
+     */
+    public synchronized void release() {
+        handle = 0;
+    }
+
+    private static native void nativeOnAudioFocusChange(long handle, int focusChange);
+}
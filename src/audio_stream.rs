@@ -1,19 +1,25 @@
+use libc::{CLOCK_BOOTTIME, CLOCK_MONOTONIC};
 use num_traits::FromPrimitive;
 use oboe_sys as ffi;
 use std::{
     ffi::c_void,
     fmt::{self, Display},
     marker::PhantomData,
-    mem::{transmute, MaybeUninit},
+    mem::{transmute, ManuallyDrop, MaybeUninit},
     ops::{Deref, DerefMut},
-    ptr::null_mut,
+    ptr::{self, null_mut},
+    result,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
 };
 
 use super::{
-    audio_stream_base_fmt, wrap_result, wrap_status, AudioApi, AudioCallbackWrapper,
-    AudioStreamBase, FrameTimestamp, Input, IsFrameType, Output, RawAudioInputStream,
-    RawAudioOutputStream, RawAudioStream, RawAudioStreamBase, Result, Status, StreamState,
-    NANOS_PER_MILLISECOND,
+    audio_stream_base_fmt, ffi_call, wrap_result, wrap_status, AudioApi, AudioCallbackWrapper,
+    AudioStreamBase, AudioStreamBuilder, ClockId, Direction, Error, FrameTimestamp, HintApi, Input,
+    IsChannelCount, IsDirection, IsFormat, IsFrameType, LatencyClass, Output, PerformanceMode,
+    RawAudioInputStream, RawAudioOutputStream, RawAudioStream, RawAudioStreamBase, Resettable,
+    Result, SharingMode, StartedStream, Status, StreamConfig, StreamHealth, StreamMetadata,
+    StreamState, Unspecified, NANOS_PER_MILLISECOND, NANOS_PER_SECOND,
 };
 
 /**
@@ -24,6 +30,70 @@ use super::{
  */
 pub const DEFAULT_TIMEOUT_NANOS: i64 = 2000 * NANOS_PER_MILLISECOND;
 
+/**
+ * The timeout of blocking read and write
+ *
+ * The raw nanoseconds and [`Duration`] can be converted into it: the zero is non-blocking
+ * and the too long durations block forever.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Timeout {
+    /**
+     * Block until all frames are transferred
+     */
+    #[default]
+    Forever,
+
+    /**
+     * Transfer only the frames which fit without waiting
+     */
+    None,
+
+    /**
+     * Block until all frames are transferred or the duration expires
+     */
+    After(Duration),
+}
+
+impl Timeout {
+    /**
+     * The timeout which is long enough to never expire
+     *
+     * It is not `i64::MAX`, because the backends add it to the current time.
+     */
+    const FOREVER_NANOS: i64 = i64::MAX / 2;
+
+    /**
+     * Get the timeout in nanoseconds which is passed to Oboe
+     */
+    pub fn as_nanos(self) -> i64 {
+        match self {
+            Timeout::Forever => Self::FOREVER_NANOS,
+            Timeout::None => 0,
+            Timeout::After(duration) => duration.as_nanos().min(Self::FOREVER_NANOS as u128) as i64,
+        }
+    }
+}
+
+impl From<Duration> for Timeout {
+    fn from(duration: Duration) -> Self {
+        Timeout::After(duration)
+    }
+}
+
+impl From<i64> for Timeout {
+    /**
+     * Convert the timeout in nanoseconds, the zero or negative is non-blocking
+     */
+    fn from(nanos: i64) -> Self {
+        if nanos > 0 {
+            Timeout::After(Duration::from_nanos(nanos as u64))
+        } else {
+            Timeout::None
+        }
+    }
+}
+
 /**
  * Safe base trait for Oboe audio stream.
  */
@@ -33,6 +103,30 @@ pub trait AudioStreamSafe: AudioStreamBase {
      */
     fn get_state(&self) -> StreamState;
 
+    /**
+     * Returns true if the stream was disconnected, e.g. when the headphones was unplugged.
+     *
+     * The disconnected stream cannot be restarted, so it should be closed and a new stream
+     * should be opened instead.
+     */
+    fn is_disconnected(&self) -> bool {
+        self.get_state() == StreamState::Disconnected
+    }
+
+    /**
+     * Returns true if the stream most likely uses the exclusive MMAP path.
+     *
+     * Oboe does not expose whether MMAP is actually used, so this is a heuristic:
+     * the stream uses [`AudioApi::AAudio`], its sharing mode is [`SharingMode::Exclusive`]
+     * and its performance mode is [`PerformanceMode::LowLatency`]. AAudio reports the modes
+     * actually granted, so a stream which fell back to the shared legacy path is not counted.
+     */
+    fn is_exclusive_used(&self) -> bool {
+        self.get_audio_api() == AudioApi::AAudio
+            && self.get_sharing_mode() == SharingMode::Exclusive
+            && self.get_performance_mode() == PerformanceMode::LowLatency
+    }
+
     /**
      * This can be used to adjust the latency of the buffer by changing
      * the threshold where blocking will occur.
@@ -43,6 +137,46 @@ pub trait AudioStreamSafe: AudioStreamBase {
      */
     fn set_buffer_size_in_frames(&mut self, _requested_frames: i32) -> Result<i32>;
 
+    /**
+     * Set the buffer size which gives the specified latency.
+     *
+     * The duration is converted to frames using the actual sample rate, rounded to the nearest
+     * multiple of burst, but at least one burst, and clamped to the buffer capacity.
+     *
+     * The system may adjust the size further, so the achieved latency may differ from
+     * the target. Returns the actual buffer size in frames.
+     */
+    fn set_target_latency(&mut self, latency: Duration) -> Result<i32> {
+        let burst = self.get_frames_per_burst().max(1) as i64;
+        let frames = self.duration_to_frames(latency);
+        let bursts = ((frames + burst / 2) / burst).max(1);
+        let capacity = self.get_buffer_capacity_in_frames() as i64;
+        let frames = if capacity > 0 {
+            (bursts * burst).min(capacity)
+        } else {
+            bursts * burst
+        };
+        self.set_buffer_size_in_frames(frames.min(i32::MAX as i64) as i32)
+    }
+
+    /**
+     * Set the buffer size to the number of bursts of the latency class.
+     *
+     * The ratios of buffer size to burst are 2 for [`LatencyClass::UltraLow`], 3 for
+     * [`LatencyClass::Low`], 4 for [`LatencyClass::Balanced`] and 8 for [`LatencyClass::Robust`].
+     * The size is clamped to the buffer capacity. Returns the actual buffer size in frames.
+     */
+    fn set_latency_class(&mut self, class: LatencyClass) -> Result<i32> {
+        let frames = self.get_frames_per_burst().max(1) as i64 * class.bursts() as i64;
+        let capacity = self.get_buffer_capacity_in_frames() as i64;
+        let frames = if capacity > 0 {
+            frames.min(capacity)
+        } else {
+            frames
+        };
+        self.set_buffer_size_in_frames(frames as i32)
+    }
+
     /**
      * An XRun is an Underrun or an Overrun.
      * During playing, an underrun will occur if the stream is not written in time
@@ -64,6 +198,36 @@ pub trait AudioStreamSafe: AudioStreamBase {
      */
     fn get_frames_per_burst(&mut self) -> i32;
 
+    /**
+     * Get the number of samples in the buffer of `multiplier` bursts
+     *
+     * It is `frames_per_burst * channels * multiplier`, which is suitable to preallocate
+     * the buffers before starting the stream, so nothing is allocated in the callback.
+     * See also [`StreamBuffers`](crate::StreamBuffers).
+     *
+     * The burst may differ when the stream is reopened, so the length must be recomputed.
+     */
+    fn recommended_buffer_len(&mut self, multiplier: usize) -> usize {
+        self.get_frames_per_burst().max(0) as usize * self.get_channel_count() as usize * multiplier
+    }
+
+    /**
+     * Get the number of bytes per sample of the negotiated format
+     *
+     * Unlike [`AudioStreamSafe::get_bytes_per_sample`] it does not need the mutable stream.
+     * The packed 24-bit samples take 3 bytes.
+     */
+    fn bytes_per_sample(&self) -> usize {
+        self.get_format().bytes_per_sample()
+    }
+
+    /**
+     * Get the number of bytes per frame of the negotiated format and channel count
+     */
+    fn bytes_per_frame(&self) -> usize {
+        self.get_channel_count() as usize * self.bytes_per_sample()
+    }
+
     /**
      * Get the number of bytes in each audio frame. This is calculated using the channel count
      * and the sample format. For example, a 2 channel floating point stream will have
@@ -114,11 +278,43 @@ pub trait AudioStreamSafe: AudioStreamBase {
      * The time is based on the implementation's best effort, using whatever knowledge is available
      * to the system, but cannot account for any delay unknown to the implementation.
      *
-     * @param clockId the type of clock to use e.g. CLOCK_MONOTONIC
+     * @param clockId the type of clock to use e.g. [`ClockId::Monotonic`]
      * @return a FrameTimestamp containing the position and time at which a particular audio frame
      * entered or left the audio processing pipeline, or an error if the operation failed.
+     *
+     * The timestamps are available only while the stream is started, otherwise
+     * [`Error::InvalidState`] is returned.
      */
-    fn get_timestamp(&mut self, clock_id: i32) -> Result<FrameTimestamp>;
+    fn get_timestamp(&mut self, clock_id: ClockId) -> Result<FrameTimestamp>;
+
+    /**
+     * Get the timestamp of the same frame using both `CLOCK_MONOTONIC` and `CLOCK_BOOTTIME`
+     *
+     * Returns the monotonic timestamp first, then the boottime one.
+     *
+     * Oboe reads the timestamp for a single clock per call, so the monotonic timestamp is read
+     * from the stream and the boottime one is derived from it. The offset between clocks is
+     * sampled once, as the boottime reading taken between two monotonic readings, so its error
+     * is at most half of the time between them, usually well below a microsecond.
+     * The clocks differ only by the time spent in suspend, so the offset does not drift while
+     * the stream is running.
+     */
+    fn get_timestamps(&mut self) -> Result<(FrameTimestamp, FrameTimestamp)> {
+        let monotonic = self.get_timestamp(ClockId::Monotonic)?;
+
+        let before = clock_nanos(CLOCK_MONOTONIC);
+        let boottime = clock_nanos(CLOCK_BOOTTIME);
+        let after = clock_nanos(CLOCK_MONOTONIC);
+        let offset = boottime - before - (after - before) / 2;
+
+        Ok((
+            monotonic,
+            FrameTimestamp {
+                position: monotonic.position,
+                timestamp: monotonic.timestamp + offset,
+            },
+        ))
+    }
 
     /**
      * Get the underlying audio API which the stream uses.
@@ -132,10 +328,85 @@ pub trait AudioStreamSafe: AudioStreamBase {
         self.get_audio_api() == AudioApi::AAudio
     }
 
+    /**
+     * Returns true if the stream can be paused, e.g. to enable the pause button.
+     *
+     * Only the output streams can be paused. Oboe implements pausing for both AAudio and
+     * OpenSL ES outputs, while pausing an input stream fails with [`Error::Unimplemented`].
+     */
+    fn can_pause(&self) -> bool {
+        self.get_direction() == Direction::Output
+            && matches!(self.get_audio_api(), AudioApi::AAudio | AudioApi::OpenSLES)
+    }
+
+    /**
+     * Returns true if the stream can be flushed.
+     *
+     * The same streams can be flushed as paused, see [`AudioStreamSafe::can_pause`].
+     * The flush succeeds only while the stream is paused.
+     */
+    fn can_flush(&self) -> bool {
+        self.can_pause()
+    }
+
     /**
      * Returns the number of frames of data currently in the buffer
      */
     fn get_available_frames(&mut self) -> Result<i32>;
+
+    /**
+     * Take the snapshot of stream parameters
+     *
+     * The parameters do not change while the stream is open, so the snapshot can be taken
+     * once after opening and shared with other threads instead of querying the stream.
+     */
+    fn metadata(&mut self) -> StreamMetadata {
+        StreamMetadata::new(self)
+    }
+
+    /**
+     * Get the health summary for logging
+     *
+     * The values which cannot be queried, e.g. the xrun count on OpenSL ES,
+     * are `None` instead of failing the whole summary.
+     */
+    fn health(&mut self) -> StreamHealth {
+        StreamHealth::new(self)
+    }
+
+    /**
+     * Convert the number of frames to duration using the actual sample rate of the stream.
+     *
+     * The result is rounded to the nearest nanosecond. Negative frame counts are treated as zero.
+     * When the sample rate is unknown, i.e. not positive, the zero duration is returned.
+     */
+    fn frames_to_duration(&self, frames: i64) -> Duration {
+        let sample_rate = self.get_sample_rate();
+        if sample_rate <= 0 {
+            return Duration::ZERO;
+        }
+        let sample_rate = sample_rate as u128;
+        let nanos = frames.max(0) as u128 * NANOS_PER_SECOND as u128;
+        Duration::from_nanos(((nanos + sample_rate / 2) / sample_rate) as u64)
+    }
+
+    /**
+     * Convert the duration to number of frames using the actual sample rate of the stream.
+     *
+     * The result is rounded to the nearest frame, so converting the result back
+     * using [`AudioStreamSafe::frames_to_duration`] gives the duration with error
+     * not exceeding half of frame period. When the sample rate is unknown, i.e. not positive,
+     * zero frames are returned.
+     */
+    fn duration_to_frames(&self, duration: Duration) -> i64 {
+        let sample_rate = self.get_sample_rate();
+        if sample_rate <= 0 {
+            return 0;
+        }
+        let sample_rate = sample_rate as u128;
+        let nanos_per_second = NANOS_PER_SECOND as u128;
+        ((duration.as_nanos() * sample_rate + nanos_per_second / 2) / nanos_per_second) as i64
+    }
 }
 
 /**
@@ -197,6 +468,18 @@ pub trait AudioStream: AudioStreamSafe {
      */
     fn request_stop(&mut self) -> Status;
 
+    /**
+     * Start the stream asynchronously and return the guard which stops it on drop.
+     *
+     * See [`StartedStream`].
+     */
+    fn started(self) -> Result<StartedStream<Self>>
+    where
+        Self: Sized,
+    {
+        StartedStream::new(self)
+    }
+
     /**
      * Wait until the stream's current state no longer matches the input state.
      * The input state is passed to avoid race conditions caused by the state
@@ -238,6 +521,83 @@ pub trait AudioStream: AudioStreamSafe {
         num_frames: i32,
         timeout_nanoseconds: i64,
     ) -> Result<i32>;
+
+    /**
+     * Get the iterator over the state transitions of the stream.
+     *
+     * The iterator repeatedly waits for the state change with `poll_interval` timeout and
+     * yields each new state until the stream is closed or disconnected. The interval shorter
+     * than 1 ms is raised to it, so the iterator does not spin. When the waiting fails because
+     * the stream was disconnected, [`StreamState::Disconnected`] is yielded as the last state.
+     * Note that it blocks the current thread while waiting.
+     */
+    fn state_changes(&mut self, poll_interval: Duration) -> StateChanges<'_, Self>
+    where
+        Self: Sized,
+    {
+        StateChanges::new(self, poll_interval)
+    }
+}
+
+/**
+ * The iterator over the state transitions of the stream
+ *
+ * See [`AudioStream::state_changes`].
+ */
+pub struct StateChanges<'s, S> {
+    stream: &'s mut S,
+    state: StreamState,
+    timeout_nanoseconds: i64,
+    finished: bool,
+}
+
+impl<'s, S: AudioStream> StateChanges<'s, S> {
+    const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    fn new(stream: &'s mut S, poll_interval: Duration) -> Self {
+        let state = stream.get_state();
+        let poll_interval = poll_interval.max(Self::MIN_POLL_INTERVAL);
+        Self {
+            stream,
+            state,
+            timeout_nanoseconds: poll_interval.as_nanos().min(i64::MAX as u128) as i64,
+            finished: is_final_state(state),
+        }
+    }
+}
+
+impl<'s, S: AudioStream> Iterator for StateChanges<'s, S> {
+    type Item = StreamState;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            match self
+                .stream
+                .wait_for_state_change(self.state, self.timeout_nanoseconds)
+            {
+                Ok(state) if state != self.state => {
+                    self.state = state;
+                    self.finished = is_final_state(state);
+                    return Some(state);
+                }
+                Ok(_) | Err(Error::Timeout) => (),
+                Err(Error::Disconnected) => {
+                    self.state = StreamState::Disconnected;
+                    self.finished = true;
+                    return Some(StreamState::Disconnected);
+                }
+                Err(_) => self.finished = true,
+            }
+        }
+        None
+    }
+}
+
+fn is_final_state(state: StreamState) -> bool {
+    matches!(
+        state,
+        StreamState::Closing | StreamState::Closed | StreamState::Disconnected
+    )
 }
 
 /**
@@ -249,6 +609,22 @@ pub trait AudioInputStreamSafe: AudioStreamSafe {
      * This monotonic counter will never get reset.
      */
     fn get_frames_read(&mut self) -> i64;
+
+    /**
+     * Calculate the input latency, i.e. the time it takes to a frame to travel from
+     * the analog-to-digital converter to the app.
+     *
+     * It is computed from the timestamp of the stream and the number of frames read, so
+     * the stream must be running and provide a valid timestamp, which is usually the case
+     * only for AAudio. Otherwise the error of [`AudioStreamSafe::get_timestamp`] is returned.
+     */
+    fn get_input_latency_millis(&mut self) -> Result<f64> {
+        let timestamp = self.get_timestamp(ClockId::Monotonic)?;
+        let frames_read = self.get_frames_read();
+        let now = clock_nanos(CLOCK_MONOTONIC);
+        let app_frame_time = frame_time(self, timestamp, frames_read)?;
+        Ok((now - app_frame_time) as f64 / NANOS_PER_MILLISECOND as f64)
+    }
 }
 
 /**
@@ -266,12 +642,16 @@ pub trait AudioInputStreamSync: AudioInputStream {
      * Read data into the supplied buffer from the stream. This method will block until the read
      * is complete or it runs out of time.
      *
-     * If `timeout_nanoseconds` is zero then this call will not wait.
+     * The `timeout` is [`Timeout`] or anything convertible into it, e.g. the nanoseconds.
+     * If it is [`Timeout::None`] then this call will not wait.
+     *
+     * When the stream was disconnected the [`Error::Disconnected`](crate::Error::Disconnected)
+     * is returned, so the read loop can be broken to reopen the stream.
      */
     fn read(
         &mut self,
         _buffer: &mut [<Self::FrameType as IsFrameType>::Type],
-        _timeout_nanoseconds: i64,
+        _timeout: impl Into<Timeout>,
     ) -> Result<i32>;
 }
 
@@ -284,6 +664,54 @@ pub trait AudioOutputStreamSafe: AudioStreamSafe {
      * This monotonic counter will never get reset.
      */
     fn get_frames_written(&mut self) -> i64;
+
+    /**
+     * The number of audio frames read from the stream by the device.
+     * This monotonic counter will never get reset.
+     *
+     * For an output stream it approximates the number of frames which was presented.
+     */
+    fn get_frames_presented(&mut self) -> i64;
+
+    /**
+     * Calculate the output latency, i.e. the time it takes to a frame to travel from
+     * the app to the digital-to-analog converter.
+     *
+     * It is computed from the timestamp of the stream and the number of frames written, so
+     * the stream must be running and provide a valid timestamp, which is usually the case
+     * only for AAudio. Otherwise the error of [`AudioStreamSafe::get_timestamp`] is returned.
+     */
+    fn get_output_latency_millis(&mut self) -> Result<f64> {
+        let timestamp = self.get_timestamp(ClockId::Monotonic)?;
+        let frames_written = self.get_frames_written();
+        let now = clock_nanos(CLOCK_MONOTONIC);
+        let app_frame_time = frame_time(self, timestamp, frames_written)?;
+        Ok((app_frame_time - now) as f64 / NANOS_PER_MILLISECOND as f64)
+    }
+}
+
+/// The time at which the frame at `position` is presented by the hardware
+fn frame_time<S: AudioStreamSafe + ?Sized>(
+    stream: &S,
+    timestamp: FrameTimestamp,
+    position: i64,
+) -> Result<i64> {
+    let sample_rate = stream.get_sample_rate() as i64;
+    if sample_rate <= 0 {
+        return Err(Error::InvalidRate);
+    }
+    Ok(timestamp.timestamp + (position - timestamp.position) * NANOS_PER_SECOND / sample_rate)
+}
+
+// The fields of `timespec` are 32-bit on 32-bit targets
+#[allow(clippy::unnecessary_cast)]
+pub(crate) fn clock_nanos(clock_id: libc::clockid_t) -> i64 {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(clock_id, &mut time) };
+    time.tv_sec as i64 * NANOS_PER_SECOND + time.tv_nsec as i64
 }
 
 /**
@@ -341,105 +769,225 @@ pub trait AudioOutputStreamSync: AudioOutputStream {
      * Write data from the supplied buffer into the stream. This method will block until the write
      * is complete or it runs out of time.
      *
-     * If `timeout_nanoseconds` is zero then this call will not wait.
+     * The `timeout` is [`Timeout`] or anything convertible into it, e.g. the nanoseconds.
+     * If it is [`Timeout::None`] then this call will not wait.
+     *
+     * When the stream was disconnected the [`Error::Disconnected`](crate::Error::Disconnected)
+     * is returned, so the write loop can be broken to reopen the stream.
      */
     fn write(
         &mut self,
         _buffer: &[<Self::FrameType as IsFrameType>::Type],
-        _timeout_nanoseconds: i64,
+        _timeout: impl Into<Timeout>,
     ) -> Result<i32>;
+
+    /**
+     * Get the number of frames which can be written without blocking
+     *
+     * It is the buffer size minus the frames queued for playback.
+     */
+    fn available_write_frames(&mut self) -> Result<i32> {
+        let queued = self.get_available_frames()?;
+        Ok((self.get_buffer_size_in_frames() - queued).max(0))
+    }
+
+    /**
+     * Write only the frames which fit into the buffer without blocking
+     *
+     * Returns the number of frames written. When the free space cannot be queried,
+     * e.g. on some OpenSL ES devices, the write with zero timeout is used instead,
+     * which is non-blocking too.
+     */
+    fn try_write(&mut self, buffer: &[<Self::FrameType as IsFrameType>::Type]) -> Result<usize> {
+        let buffer = match self.available_write_frames() {
+            Ok(available) => &buffer[..buffer.len().min(available as usize)],
+            Err(Error::Disconnected) => return Err(Error::Disconnected),
+            Err(_) => buffer,
+        };
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        self.write(buffer, Timeout::None)
+            .map(|frames| frames as usize)
+    }
+
+    /**
+     * Write all frames, blocking until they are written or the overall timeout expires
+     *
+     * The partial writes are repeated with the remaining time budget. When the timeout expires
+     * before all frames are written, [`Error::Timeout`] is returned. The errors, e.g.
+     * [`Error::Disconnected`], are returned together with the number of frames
+     * written so far.
+     */
+    fn write_all(
+        &mut self,
+        buffer: &[<Self::FrameType as IsFrameType>::Type],
+        timeout: Duration,
+    ) -> std::result::Result<(), PartialWrite> {
+        let deadline = Instant::now() + timeout;
+        let mut written = 0;
+
+        while written < buffer.len() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.write(&buffer[written..], remaining) {
+                Ok(frames) => written += frames as usize,
+                Err(error) => return Err(PartialWrite { error, written }),
+            }
+
+            if written < buffer.len() && Instant::now() >= deadline {
+                return Err(PartialWrite {
+                    error: Error::Timeout,
+                    written,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * The error of [`AudioOutputStreamSync::write_all`] with the number of frames written before it
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialWrite {
+    /**
+     * The error which interrupted writing
+     */
+    pub error: Error,
+
+    /**
+     * The number of frames written before the error
+     */
+    pub written: usize,
+}
+
+impl From<PartialWrite> for Error {
+    fn from(partial: PartialWrite) -> Self {
+        partial.error
+    }
 }
 
+impl Display for PartialWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} after {} frames written", self.error, self.written)
+    }
+}
+
+impl std::error::Error for PartialWrite {}
+
 impl<T: RawAudioStream + RawAudioStreamBase> AudioStreamSafe for T {
     fn set_buffer_size_in_frames(&mut self, requested_frames: i32) -> Result<i32> {
         wrap_result(unsafe {
-            ffi::oboe_AudioStream_setBufferSizeInFrames(self._raw_stream_mut(), requested_frames)
+            ffi_call!(oboe_AudioStream_setBufferSizeInFrames(
+                self._raw_stream_mut(),
+                requested_frames
+            ))
         })
     }
 
     fn get_state(&self) -> StreamState {
         FromPrimitive::from_i32(unsafe {
-            ffi::oboe_AudioStream_getState(self._raw_stream() as *const _ as *mut _)
+            ffi_call!(oboe_AudioStream_getState(
+                self._raw_stream() as *const _ as *mut _
+            ))
         })
         .unwrap()
     }
 
     fn get_xrun_count(&self) -> Result<i32> {
         wrap_result(unsafe {
-            ffi::oboe_AudioStream_getXRunCount(self._raw_stream() as *const _ as *mut _)
+            ffi_call!(oboe_AudioStream_getXRunCount(
+                self._raw_stream() as *const _ as *mut _
+            ))
         })
     }
 
     fn is_xrun_count_supported(&self) -> bool {
-        unsafe { ffi::oboe_AudioStream_isXRunCountSupported(self._raw_stream()) }
+        unsafe { ffi_call!(oboe_AudioStream_isXRunCountSupported(self._raw_stream())) }
     }
 
     fn get_frames_per_burst(&mut self) -> i32 {
-        unsafe { ffi::oboe_AudioStream_getFramesPerBurst(self._raw_stream_mut()) }
+        unsafe { ffi_call!(oboe_AudioStream_getFramesPerBurst(self._raw_stream_mut())) }
     }
 
     fn get_bytes_per_sample(&mut self) -> i32 {
-        unsafe { ffi::oboe_AudioStream_getBytesPerSample(self._raw_stream_mut()) }
+        unsafe { ffi_call!(oboe_AudioStream_getBytesPerSample(self._raw_stream_mut())) }
     }
 
     fn calculate_latency_millis(&mut self) -> Result<f64> {
-        wrap_result(unsafe { ffi::oboe_AudioStream_calculateLatencyMillis(self._raw_stream_mut()) })
+        wrap_result(unsafe {
+            ffi_call!(oboe_AudioStream_calculateLatencyMillis(
+                self._raw_stream_mut()
+            ))
+        })
     }
 
-    fn get_timestamp(&mut self, clock_id: i32 /* clockid_t */) -> Result<FrameTimestamp> {
+    fn get_timestamp(&mut self, clock_id: ClockId) -> Result<FrameTimestamp> {
+        // The position would be stale or undefined before the stream is started
+        if self.get_state() != StreamState::Started {
+            return Err(Error::InvalidState);
+        }
+
         wrap_result(unsafe {
-            transmute(ffi::oboe_AudioStream_getTimestamp(
+            transmute(ffi_call!(oboe_AudioStream_getTimestamp(
                 self._raw_stream_mut() as *mut _ as *mut c_void,
-                clock_id,
-            ))
+                clock_id as i32,
+            )))
         })
     }
 
     fn get_audio_api(&self) -> AudioApi {
-        FromPrimitive::from_i32(unsafe { ffi::oboe_AudioStream_getAudioApi(self._raw_stream()) })
-            .unwrap()
+        FromPrimitive::from_i32(unsafe {
+            ffi_call!(oboe_AudioStream_getAudioApi(self._raw_stream()))
+        })
+        .unwrap()
     }
 
     fn get_available_frames(&mut self) -> Result<i32> {
-        wrap_result(unsafe { ffi::oboe_AudioStream_getAvailableFrames(self._raw_stream_mut()) })
+        wrap_result(unsafe {
+            ffi_call!(oboe_AudioStream_getAvailableFrames(self._raw_stream_mut()))
+        })
     }
 }
 
 impl<T: RawAudioStream + RawAudioStreamBase> AudioStream for T {
     fn open(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_open(self._raw_stream_mut()) })
+        wrap_status(unsafe { ffi_call!(oboe_AudioStream_open(self._raw_stream_mut())) })
     }
 
     fn close(&mut self) -> Status {
         wrap_status(unsafe {
-            ffi::oboe_AudioStream_close(self._raw_stream_mut() as *mut _ as *mut c_void)
+            ffi_call!(oboe_AudioStream_close(
+                self._raw_stream_mut() as *mut _ as *mut c_void
+            ))
         })
     }
 
     fn start_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status {
         wrap_status(unsafe {
-            ffi::oboe_AudioStream_start(
+            ffi_call!(oboe_AudioStream_start(
                 self._raw_stream_mut() as *mut _ as *mut c_void,
                 timeout_nanoseconds,
-            )
+            ))
         })
     }
 
     fn stop_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status {
         wrap_status(unsafe {
-            ffi::oboe_AudioStream_stop(
+            ffi_call!(oboe_AudioStream_stop(
                 self._raw_stream_mut() as *mut _ as *mut c_void,
                 timeout_nanoseconds,
-            )
+            ))
         })
     }
 
     fn request_start(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_requestStart(self._raw_stream_mut()) })
+        wrap_status(unsafe { ffi_call!(oboe_AudioStream_requestStart(self._raw_stream_mut())) })
     }
 
     fn request_stop(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_requestStop(self._raw_stream_mut()) })
+        wrap_status(unsafe { ffi_call!(oboe_AudioStream_requestStop(self._raw_stream_mut())) })
     }
 
     fn wait_for_state_change(
@@ -449,12 +997,12 @@ impl<T: RawAudioStream + RawAudioStreamBase> AudioStream for T {
     ) -> Result<StreamState> {
         let mut next_state = MaybeUninit::<StreamState>::uninit();
         wrap_status(unsafe {
-            ffi::oboe_AudioStream_waitForStateChange(
+            ffi_call!(oboe_AudioStream_waitForStateChange(
                 self._raw_stream_mut(),
                 input_state as i32,
                 next_state.as_mut_ptr() as *mut i32,
                 timeout_nanoseconds,
-            )
+            ))
         })
         .map(|_| unsafe { next_state.assume_init() })
     }
@@ -465,11 +1013,11 @@ impl<T: RawAudioStream + RawAudioStreamBase> AudioStream for T {
         timeout_nanoseconds: i64,
     ) -> Result<i32> {
         wrap_result(unsafe {
-            ffi::oboe_AudioStream_waitForAvailableFrames(
+            ffi_call!(oboe_AudioStream_waitForAvailableFrames(
                 self._raw_stream_mut(),
                 num_frames,
                 timeout_nanoseconds,
-            )
+            ))
         })
     }
 }
@@ -477,7 +1025,9 @@ impl<T: RawAudioStream + RawAudioStreamBase> AudioStream for T {
 impl<T: RawAudioInputStream + RawAudioStream + RawAudioStreamBase> AudioInputStreamSafe for T {
     fn get_frames_read(&mut self) -> i64 {
         unsafe {
-            ffi::oboe_AudioStream_getFramesRead(self._raw_stream_mut() as *mut _ as *mut c_void)
+            ffi_call!(oboe_AudioStream_getFramesRead(
+                self._raw_stream_mut() as *mut _ as *mut c_void
+            ))
         }
     }
 }
@@ -487,7 +1037,17 @@ impl<T: RawAudioInputStream + RawAudioStream + RawAudioStreamBase> AudioInputStr
 impl<T: RawAudioOutputStream + RawAudioStream + RawAudioStreamBase> AudioOutputStreamSafe for T {
     fn get_frames_written(&mut self) -> i64 {
         unsafe {
-            ffi::oboe_AudioStream_getFramesWritten(self._raw_stream_mut() as *mut _ as *mut c_void)
+            ffi_call!(oboe_AudioStream_getFramesWritten(
+                self._raw_stream_mut() as *mut _ as *mut c_void
+            ))
+        }
+    }
+
+    fn get_frames_presented(&mut self) -> i64 {
+        unsafe {
+            ffi_call!(oboe_AudioStream_getFramesRead(
+                self._raw_stream_mut() as *mut _ as *mut c_void
+            ))
         }
     }
 }
@@ -495,28 +1055,28 @@ impl<T: RawAudioOutputStream + RawAudioStream + RawAudioStreamBase> AudioOutputS
 impl<T: RawAudioOutputStream + RawAudioStream + RawAudioStreamBase> AudioOutputStream for T {
     fn pause_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status {
         wrap_status(unsafe {
-            ffi::oboe_AudioStream_pause(
+            ffi_call!(oboe_AudioStream_pause(
                 self._raw_stream_mut() as *mut _ as *mut c_void,
                 timeout_nanoseconds,
-            )
+            ))
         })
     }
 
     fn flush_with_timeout(&mut self, timeout_nanoseconds: i64) -> Status {
         wrap_status(unsafe {
-            ffi::oboe_AudioStream_flush(
+            ffi_call!(oboe_AudioStream_flush(
                 self._raw_stream_mut() as *mut _ as *mut c_void,
                 timeout_nanoseconds,
-            )
+            ))
         })
     }
 
     fn request_pause(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_requestPause(self._raw_stream_mut()) })
+        wrap_status(unsafe { ffi_call!(oboe_AudioStream_requestPause(self._raw_stream_mut())) })
     }
 
     fn request_flush(&mut self) -> Status {
-        wrap_status(unsafe { ffi::oboe_AudioStream_requestFlush(self._raw_stream_mut()) })
+        wrap_status(unsafe { ffi_call!(oboe_AudioStream_requestFlush(self._raw_stream_mut())) })
     }
 }
 
@@ -554,7 +1114,7 @@ impl Default for AudioStreamHandle {
 
 impl Drop for AudioStreamHandle {
     fn drop(&mut self) {
-        unsafe { ffi::oboe_AudioStream_delete(self.0) }
+        unsafe { ffi_call!(oboe_AudioStream_delete(self.0)) }
     }
 }
 
@@ -620,15 +1180,59 @@ impl<'s> RawAudioInputStream for AudioStreamRef<'s, Input> {}
 
 impl<'s> RawAudioOutputStream for AudioStreamRef<'s, Output> {}
 
+/// The id and the opening time which tag the opened stream in logs
+#[derive(Clone, Copy)]
+struct StreamIdentity {
+    id: u64,
+    created_at: Instant,
+}
+
+impl StreamIdentity {
+    fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            created_at: Instant::now(),
+        }
+    }
+}
+
 /**
  * The audio stream for asynchronous (callback-driven) mode
+ *
+ * On drop the stream is closed first, which synchronously stops the callbacks,
+ * and only then the callback is dropped.
  */
 pub struct AudioStreamAsync<D, F> {
+    // Fields are dropped in declaration order, so the stream is deleted before the callback
     raw: AudioStreamHandle,
 
     // Needed to keep callback alive
-    #[allow(dead_code)]
     callback: AudioCallbackWrapper<D, F>,
+
+    config: StreamConfig,
+
+    identity: StreamIdentity,
+}
+
+/// Close the dropped stream
+///
+/// Dropping the stream which was never started is likely a misuse, so it is logged
+/// in debug builds with `log` feature.
+fn close_on_drop<T: AudioStream>(stream: &mut T) {
+    #[cfg(all(feature = "log", debug_assertions))]
+    if stream.get_state() == StreamState::Open {
+        log::warn!("The audio stream is dropped without being started");
+    }
+    let _ = stream.close();
+}
+
+impl<D, F> Drop for AudioStreamAsync<D, F> {
+    fn drop(&mut self) {
+        // Closing waits for the callback in progress to finish
+        close_on_drop(self);
+    }
 }
 
 impl<D, F> fmt::Debug for AudioStreamAsync<D, F> {
@@ -641,11 +1245,162 @@ impl<D, F> AudioStreamAsync<D, F> {
     pub(crate) fn wrap_raw(
         raw: *mut ffi::oboe_AudioStream,
         callback: AudioCallbackWrapper<D, F>,
+        config: StreamConfig,
     ) -> Self {
-        Self {
+        let mut stream = Self {
             raw: raw.into(),
             callback,
+            config,
+            identity: StreamIdentity::next(),
+        };
+        stream.reserve_callback_frames();
+        stream
+    }
+
+    /// Let the callback preallocate for the largest buffer which the stream may pass to it
+    fn reserve_callback_frames(&mut self) {
+        let frames = self
+            .get_buffer_capacity_in_frames()
+            .max(self.get_frames_per_callback())
+            .max(0);
+        self.callback.reserve_frames(frames as usize);
+    }
+
+    /**
+     * Get the settings which was used to open the stream
+     */
+    pub fn config(&self) -> &StreamConfig {
+        &self.config
+    }
+
+    /**
+     * Get the id of stream which is unique within the process
+     *
+     * The ids are assigned in the order of opening, starting from 1. The stream gets
+     * the new id when it is reopened by [`AudioStreamAsync::reconnect`].
+     */
+    pub fn stream_id(&self) -> u64 {
+        self.identity.id
+    }
+
+    /**
+     * Get the time when the stream was opened or last reconnected
+     */
+    pub fn created_at(&self) -> Instant {
+        self.identity.created_at
+    }
+
+    /**
+     * Check that the callback panicked
+     *
+     * The panics are caught to prevent unwinding into C++ code. After panic the callback
+     * is not called anymore and data callback returns
+     * [`DataCallbackResult::Stop`](crate::DataCallbackResult::Stop), so
+     * the stream stops.
+     */
+    pub fn is_callback_panicked(&self) -> bool {
+        self.callback.panic().is_panicked()
+    }
+
+    /**
+     * Take the message of panic which occurred in the callback
+     */
+    pub fn take_callback_panic(&self) -> Option<String> {
+        self.callback.panic().take_message()
+    }
+
+    /**
+     * Get the error which was reported to the error callback
+     *
+     * It is captured before [`on_error_before_close`](crate::AudioOutputCallback::on_error_before_close)
+     * is called, so after [`AudioStreamSafe::get_state`] returns
+     * [`StreamState::Disconnected`] it tells whether the device was changed
+     * ([`Error::Disconnected`]) or the stream failed for another reason.
+     *
+     * Returns `None` when no error was reported.
+     */
+    pub fn last_error_code(&self) -> Option<Error> {
+        self.callback.error().get()
+    }
+}
+
+impl<D: IsDirection, F> AudioStreamAsync<D, F> {
+    /**
+     * Reopen and restart the stream with the same settings and callback
+     *
+     * It is intended to recover after the disconnection, e.g. when the headphones was
+     * unplugged, see [`AudioStreamSafe::is_disconnected`]. The old stream is closed, then
+     * the new one is opened using the saved [`StreamConfig`].
+     *
+     * When the stream cannot be opened with the saved config, e.g. the requested device is gone
+     * or does not support the requested sample rate, the default device with its native sample
+     * rate is tried instead, and the saved config is updated. So the sample rate may change, and
+     * the callback should take it from the stream passed to it.
+     *
+     * Must not be called from the callback.
+     */
+    pub fn reconnect(&mut self) -> Status {
+        let _ = self.close();
+
+        let raw = match AudioStreamBuilder::<D, Unspecified, Unspecified>::from_config(&self.config)
+            .open_with_callback(&mut self.callback)
+        {
+            Ok(raw) => raw,
+            Err(_) => {
+                let config = self.config.adapted();
+                let raw = AudioStreamBuilder::<D, Unspecified, Unspecified>::from_config(&config)
+                    .open_with_callback(&mut self.callback)?;
+                // Follow the default device from now on
+                self.config = config;
+                raw
+            }
+        };
+
+        // The old stream is deleted here
+        self.raw = raw.into();
+        self.identity = StreamIdentity::next();
+        self.callback.reset_stream_state();
+        self.reserve_callback_frames();
+
+        self.start()
+    }
+}
+
+impl<D, F> AudioStreamAsync<D, F> {
+    /**
+     * Report the work duration of data callback to the scheduler
+     *
+     * The Android Dynamic Performance Framework (ADPF) adjusts the clocks of CPU to let
+     * the callback thread finish its work in the `target` duration, which is typically the
+     * duration of burst. The hint session is created for the callback thread on the next
+     * callback, then the actual duration of each callback is reported, which costs two clock
+     * reads per callback.
+     *
+     * The native API of ADPF is available since Android 13 (API 33), although the Java API
+     * appeared in Android 12 (API 31). When the API is not available, the hints are silently
+     * not reported. The zero duration disables the reporting.
+     */
+    pub fn enable_performance_hints(&mut self, target: Duration) {
+        let target = target.as_nanos().min(i64::MAX as u128) as i64;
+        if target > 0 {
+            // The library is loaded here, so the callback only creates the session
+            HintApi::load();
         }
+        self.callback.performance_hint_target().set(target);
+    }
+}
+
+impl<D, F: Resettable> AudioStreamAsync<D, F> {
+    /**
+     * Stop the stream and reset the callback
+     *
+     * The callback is reset only after the stream is stopped, so the stale state does not
+     * leak into the next start.
+     */
+    pub fn stop_and_reset(&mut self) -> Status {
+        self.stop()?;
+        self.callback.callback_mut().reset();
+        Ok(())
     }
 }
 
@@ -678,9 +1433,21 @@ impl<F> RawAudioOutputStream for AudioStreamAsync<Output, F> {}
  */
 pub struct AudioStreamSync<D, F> {
     raw: AudioStreamHandle,
+    identity: StreamIdentity,
     _phantom: PhantomData<(D, F)>,
 }
 
+// The stream has a single owner, so it can be safely moved to another thread,
+// e.g. into the callback of another stream.
+unsafe impl<D, F> Send for AudioStreamSync<D, F> {}
+
+impl<D, F> Drop for AudioStreamSync<D, F> {
+    fn drop(&mut self) {
+        // The stream must be closed before deleting, even when it was never started
+        close_on_drop(self);
+    }
+}
+
 impl<D, F> fmt::Debug for AudioStreamSync<D, F> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         audio_stream_fmt(self, f)
@@ -691,9 +1458,51 @@ impl<D, F> AudioStreamSync<D, F> {
     pub(crate) fn wrap_raw(raw: *mut ffi::oboe_AudioStream) -> Self {
         Self {
             raw: raw.into(),
+            identity: StreamIdentity::next(),
             _phantom: PhantomData,
         }
     }
+
+    /**
+     * Get the id of stream which is unique within the process
+     *
+     * The ids are assigned in the order of opening, starting from 1.
+     */
+    pub fn stream_id(&self) -> u64 {
+        self.identity.id
+    }
+
+    /**
+     * Get the time when the stream was opened
+     */
+    pub fn created_at(&self) -> Instant {
+        self.identity.created_at
+    }
+}
+
+impl<D, C: IsChannelCount> AudioStreamSync<D, (Unspecified, C)> {
+    /**
+     * Convert the stream opened with unspecified format to the stream of negotiated format
+     *
+     * Returns [`Error::InvalidFormat`] together with the original stream when the format
+     * of stream, see [`AudioStreamBase::get_format`], is not `T`, so the other acceptable
+     * format can be tried.
+     */
+    pub fn reinterpret_format<T: IsFormat>(
+        self,
+    ) -> result::Result<AudioStreamSync<D, (T, C)>, (Self, Error)> {
+        if self.get_format() != T::FORMAT {
+            return Err((self, Error::InvalidFormat));
+        }
+
+        let stream = ManuallyDrop::new(self);
+        Ok(AudioStreamSync {
+            // The handle is moved out of the stream which is not dropped
+            raw: unsafe { ptr::read(&stream.raw) },
+            identity: stream.identity,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 impl<D, T> RawAudioStreamBase for AudioStreamSync<D, T> {
@@ -726,16 +1535,17 @@ impl<F: IsFrameType> AudioInputStreamSync for AudioStreamSync<Input, F> {
     fn read(
         &mut self,
         buffer: &mut [<Self::FrameType as IsFrameType>::Type],
-        timeout_nanoseconds: i64,
+        timeout: impl Into<Timeout>,
     ) -> Result<i32> {
-        wrap_result(unsafe {
-            ffi::oboe_AudioStream_read(
+        let result = wrap_result(unsafe {
+            ffi_call!(oboe_AudioStream_read(
                 &mut *self.raw,
                 buffer.as_mut_ptr() as *mut c_void,
                 buffer.len() as i32,
-                timeout_nanoseconds,
-            )
-        })
+                timeout.into().as_nanos(),
+            ))
+        });
+        detect_disconnect(self, result)
     }
 }
 
@@ -745,15 +1555,78 @@ impl<F: IsFrameType> AudioOutputStreamSync for AudioStreamSync<Output, F> {
     fn write(
         &mut self,
         buffer: &[<Self::FrameType as IsFrameType>::Type],
-        timeout_nanoseconds: i64,
+        timeout: impl Into<Timeout>,
     ) -> Result<i32> {
-        wrap_result(unsafe {
-            ffi::oboe_AudioStream_write(
+        let result = wrap_result(unsafe {
+            ffi_call!(oboe_AudioStream_write(
                 &mut *self.raw,
                 buffer.as_ptr() as *const c_void,
                 buffer.len() as i32,
-                timeout_nanoseconds,
-            )
-        })
+                timeout.into().as_nanos(),
+            ))
+        });
+        detect_disconnect(self, result)
+    }
+}
+
+/// Some backends report the disconnection as a generic failure,
+/// so the stream state is checked to surface it uniformly.
+fn detect_disconnect<S: AudioStreamSafe>(stream: &S, result: Result<i32>) -> Result<i32> {
+    match result {
+        Err(error) if error != Error::Disconnected && stream.is_disconnected() => {
+            Err(Error::Disconnected)
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_stream::TestStream;
+
+    #[test]
+    fn frames_duration_round_trip() {
+        let mut stream = TestStream::new();
+        for &sample_rate in &[8000, 44100, 48000, 96000] {
+            stream.sample_rate = sample_rate;
+            for &frames in &[0, 1, 192, 441, 48000, 1 << 40] {
+                let duration = stream.frames_to_duration(frames);
+                assert_eq!(stream.duration_to_frames(duration), frames);
+            }
+        }
+
+        stream.sample_rate = 48000;
+        assert_eq!(stream.frames_to_duration(48), Duration::from_millis(1));
+        assert_eq!(stream.duration_to_frames(Duration::from_millis(1)), 48);
+        assert_eq!(stream.frames_to_duration(-1), Duration::ZERO);
+    }
+
+    #[test]
+    fn closes_never_started_stream_on_drop() {
+        let mut stream = TestStream::new();
+        close_on_drop(&mut stream);
+        assert_eq!(stream.closed.load(Ordering::SeqCst), 1);
+        assert_eq!(stream.get_state(), StreamState::Closed);
+    }
+
+    #[test]
+    fn frames_duration_unknown_rate() {
+        let mut stream = TestStream::new();
+        for &sample_rate in &[0, -1] {
+            stream.sample_rate = sample_rate;
+            assert_eq!(stream.frames_to_duration(48000), Duration::ZERO);
+            assert_eq!(stream.duration_to_frames(Duration::from_secs(1)), 0);
+        }
+    }
+
+    #[test]
+    fn state_changes_yield_disconnect() {
+        let mut stream = TestStream::new();
+        stream.state = StreamState::Started;
+        stream.wait_error = Some(Error::Disconnected);
+
+        let states: Vec<_> = stream.state_changes(Duration::from_secs(0)).collect();
+        assert_eq!(states, [StreamState::Disconnected]);
     }
 }
@@ -85,6 +85,14 @@ pub trait AudioStreamBase {
      */
     fn get_session_id(&self) -> SessionId;
 
+    /**
+     * Get the session ID allocated for the opened stream
+     *
+     * It is available only when the stream was opened with [`SessionId::Allocate`].
+     * The ID can be used to attach the Java `AudioEffect`s to the stream.
+     */
+    fn get_allocated_session_id(&self) -> Option<i32>;
+
     /**
      * Return true if can convert channel counts to achieve optimal results.
      */
@@ -155,7 +163,17 @@ impl<T: RawAudioStreamBase> AudioStreamBase for T {
     }
 
     fn get_session_id(&self) -> SessionId {
-        FromPrimitive::from_i32(self._raw_base().mSessionId).unwrap()
+        // The opened stream stores the allocated ID instead of strategy
+        FromPrimitive::from_i32(self._raw_base().mSessionId).unwrap_or(SessionId::Allocate)
+    }
+
+    fn get_allocated_session_id(&self) -> Option<i32> {
+        let session_id = self._raw_base().mSessionId;
+        if session_id > 0 {
+            Some(session_id)
+        } else {
+            None
+        }
     }
 
     fn is_channel_conversion_allowed(&self) -> bool {
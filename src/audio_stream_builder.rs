@@ -8,10 +8,13 @@ use std::{
 };
 
 use super::{
-    audio_stream_base_fmt, wrap_status, AudioApi, AudioCallbackWrapper, AudioInputCallback,
-    AudioOutputCallback, AudioStreamAsync, AudioStreamSync, ContentType, Input, InputPreset,
-    IsChannelCount, IsDirection, IsFormat, IsFrameType, Mono, Output, PerformanceMode,
-    RawAudioStreamBase, Result, SampleRateConversionQuality, SessionId, SharingMode, Stereo,
+    audio_stream_base_fmt, ffi_call, validate_config, wrap_status, AudioApi, AudioCallbackWrapper,
+    AudioInputCallback, AudioInputFn, AudioOutputCallback, AudioOutputFn, AudioStream,
+    AudioStreamAsync, AudioStreamBase, AudioStreamSafe, AudioStreamSync, ConfigWarning,
+    ContentType, ConversionQuality, DataCallbackResult, DefaultStreamValues, Error, Input,
+    InputPreset, IsChannelCount, IsDirection, IsFormat, IsFrame, IsFrameType, Mono, OpenOutcome,
+    Output, PerformanceMode, RawAudioStreamBase, Result, SampleRateConversionQuality, SessionId,
+    SharingMode, SoftwareConversion, StartedStream, Status, Stereo, StreamConfig, StreamParameters,
     Unspecified, Usage,
 };
 
@@ -20,13 +23,13 @@ struct AudioStreamBuilderHandle(*mut ffi::oboe_AudioStreamBuilder);
 
 impl Default for AudioStreamBuilderHandle {
     fn default() -> Self {
-        Self(unsafe { ffi::oboe_AudioStreamBuilder_new() })
+        Self(unsafe { ffi_call!(oboe_AudioStreamBuilder_new()) })
     }
 }
 
 impl Drop for AudioStreamBuilderHandle {
     fn drop(&mut self) {
-        unsafe { ffi::oboe_AudioStreamBuilder_delete(self.0) }
+        unsafe { ffi_call!(oboe_AudioStreamBuilder_delete(self.0)) }
     }
 }
 
@@ -47,9 +50,9 @@ impl DerefMut for AudioStreamBuilderHandle {
 /**
  * Factory for an audio stream.
  */
-#[repr(transparent)]
 pub struct AudioStreamBuilder<D, C, T> {
     raw: AudioStreamBuilderHandle,
+    bursts: DeferredBursts,
     _phantom: PhantomData<(D, C, T)>,
 }
 
@@ -76,11 +79,182 @@ impl Default for AudioStreamBuilder<Output, Unspecified, Unspecified> {
     fn default() -> Self {
         Self {
             raw: Default::default(),
+            bursts: Default::default(),
             _phantom: PhantomData,
         }
     }
 }
 
+impl AudioStreamBuilder<Output, Unspecified, Unspecified> {
+    /**
+     * Estimate the number of low latency streams which can be opened simultaneously.
+     *
+     * This is a best-effort probe: it opens exclusive low latency output streams one by one
+     * until the system refuses to give more, then closes all of them and returns the count.
+     * The probing stops at `limit` streams.
+     *
+     * The limit is device-specific and neither Oboe nor AAudio exposes it directly. The only way
+     * to know it is to fail opening a stream with [`Error::NoFreeHandles`] or
+     * [`Error::Unavailable`], or to get a shared stream instead of the requested exclusive one.
+     * So the returned value may change at any time, e.g. when other applications open streams.
+     * The streams which this application already has open are not counted, so the result
+     * is lower than the total limit then. Only the output streams are probed, the limit for
+     * the input streams may differ.
+     *
+     * Each probe stream holds the exclusive endpoint until it is closed, so all of them are
+     * closed before returning. Other errors are returned as is.
+     */
+    pub fn max_low_latency_streams(limit: usize) -> Result<usize> {
+        let mut streams = Vec::with_capacity(limit);
+
+        let result = loop {
+            if streams.len() >= limit {
+                break Ok(());
+            }
+
+            let mut stream = match Self::default()
+                .set_performance_mode(PerformanceMode::LowLatency)
+                .set_exclusive()
+                .open_stream()
+            {
+                Ok(stream) => stream,
+                Err(Error::NoFreeHandles) | Err(Error::Unavailable) => break Ok(()),
+                Err(error) => break Err(error),
+            };
+
+            if stream.get_sharing_mode() != SharingMode::Exclusive
+                || stream.get_performance_mode() != PerformanceMode::LowLatency
+            {
+                let _ = stream.close();
+                break Ok(());
+            }
+
+            streams.push(stream);
+        };
+
+        let count = streams.len();
+        // The probe streams are never started, so they are closed explicitly
+        for mut stream in streams {
+            let _ = stream.close();
+        }
+
+        result.map(|_| count)
+    }
+
+    /**
+     * Create the builder of output stream for interactive game audio
+     *
+     * Sets usage to [`Usage::Game`], content type to [`ContentType::Sonification`],
+     * performance mode to [`PerformanceMode::LowLatency`] and sharing mode to
+     * [`SharingMode::Exclusive`]. Oboe falls back to the shared mode when the exclusive one
+     * is unavailable.
+     */
+    pub fn preset_low_latency_game() -> Self {
+        Self::default()
+            .set_usage(Usage::Game)
+            .set_content_type(ContentType::Sonification)
+            .set_performance_mode(PerformanceMode::LowLatency)
+            .set_exclusive()
+    }
+
+    /**
+     * Create the builder of output stream for music playback
+     *
+     * Sets usage to [`Usage::Media`], content type to [`ContentType::Music`] and
+     * performance mode to [`PerformanceMode::PowerSaving`], since playback is not interactive
+     * and bigger buffers save battery.
+     */
+    pub fn preset_music_playback() -> Self {
+        Self::default()
+            .set_usage(Usage::Media)
+            .set_content_type(ContentType::Music)
+            .set_performance_mode(PerformanceMode::PowerSaving)
+    }
+
+    /**
+     * Create the builder of output stream for the far end of voice call
+     *
+     * Sets usage to [`Usage::VoiceCommunication`], content type to [`ContentType::Speech`],
+     * performance mode to [`PerformanceMode::LowLatency`] and input preset to
+     * [`InputPreset::VoiceCommunication`]. The input preset is ignored by output streams,
+     * so the builder of capture stream with echo cancellation is obtained by
+     * [`set_input`](AudioStreamBuilder::set_input).
+     */
+    pub fn preset_voice_call() -> Self {
+        Self::default()
+            .set_usage(Usage::VoiceCommunication)
+            .set_content_type(ContentType::Speech)
+            .set_performance_mode(PerformanceMode::LowLatency)
+            .set_input_preset(InputPreset::VoiceCommunication)
+    }
+
+    /**
+     * Create the builder of input stream for acoustic measurement
+     *
+     * Sets input preset to [`InputPreset::Unprocessed`], performance mode to
+     * [`PerformanceMode::LowLatency`], disallows channel and format conversions and
+     * sets sample rate conversion quality to [`SampleRateConversionQuality::None`],
+     * so the captured signal is not altered on the way.
+     */
+    pub fn preset_measurement() -> AudioStreamBuilder<Input, Unspecified, Unspecified> {
+        Self::default()
+            .set_input()
+            .set_input_preset(InputPreset::Unprocessed)
+            .set_performance_mode(PerformanceMode::LowLatency)
+            .set_channel_conversion_allowed(false)
+            .set_format_conversion_allowed(false)
+            .set_sample_rate_conversion_quality(SampleRateConversionQuality::None)
+    }
+}
+
+impl<D: IsDirection> AudioStreamBuilder<D, Unspecified, Unspecified> {
+    /// Create the builder with the settings of config
+    pub(crate) fn from_config(config: &StreamConfig) -> Self {
+        let mut builder = AudioStreamBuilder::<Output, Unspecified, Unspecified>::default()
+            .set_direction::<D>()
+            .set_audio_api(config.audio_api)
+            .set_sample_rate(config.sample_rate)
+            .set_frames_per_callback(config.frames_per_callback)
+            .set_buffer_capacity_in_frames(config.buffer_capacity_in_frames)
+            .set_sharing_mode(config.sharing_mode)
+            .set_performance_mode(config.performance_mode)
+            .set_usage(config.usage)
+            .set_content_type(config.content_type)
+            .set_input_preset(config.input_preset)
+            .set_session_id(config.session_id)
+            .set_device_id(config.device_id)
+            .set_channel_conversion_allowed(config.channel_conversion_allowed)
+            .set_format_conversion_allowed(config.format_conversion_allowed)
+            .set_sample_rate_conversion_quality(config.sample_rate_conversion_quality);
+        builder._raw_base_mut().mChannelCount = config.channel_count as i32;
+        builder._raw_base_mut().mFormat = config.format as i32;
+        builder
+    }
+
+    /// Open the raw stream which uses the existing callback
+    pub(crate) fn open_with_callback<F>(
+        mut self,
+        callback: &mut AudioCallbackWrapper<D, F>,
+    ) -> Result<*mut ffi::oboe_AudioStream> {
+        let mut stream = MaybeUninit::<*mut ffi::oboe_AudioStream>::uninit();
+
+        unsafe {
+            ffi_call!(oboe_AudioStreamBuilder_setCallback(
+                &mut *self.raw,
+                callback.raw_callback()
+            ));
+        }
+
+        wrap_status(unsafe {
+            ffi_call!(oboe_AudioStreamBuilder_openStream(
+                &mut *self.raw,
+                stream.as_mut_ptr()
+            ))
+        })
+        .map(|_| unsafe { stream.assume_init() })
+    }
+}
+
 impl<D, C, T> From<AudioStreamBuilder<D, C, T>> for AudioStreamBuilderHandle {
     fn from(builder: AudioStreamBuilder<D, C, T>) -> Self {
         builder.raw
@@ -89,8 +263,31 @@ impl<D, C, T> From<AudioStreamBuilder<D, C, T>> for AudioStreamBuilderHandle {
 
 impl<D, C, T> AudioStreamBuilder<D, C, T> {
     fn convert<D1, C1, T1>(self) -> AudioStreamBuilder<D1, C1, T1> {
+        let bursts = self.bursts;
         AudioStreamBuilder {
             raw: self.into(),
+            bursts,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn set_callback_wrapper<F>(
+        self,
+        mut callback: AudioCallbackWrapper<D, F>,
+    ) -> AudioStreamBuilderAsync<D, F> {
+        let Self {
+            mut raw, bursts, ..
+        } = self;
+        unsafe {
+            ffi_call!(oboe_AudioStreamBuilder_setCallback(
+                &mut *raw,
+                callback.raw_callback()
+            ));
+        }
+        AudioStreamBuilderAsync {
+            raw,
+            callback,
+            bursts,
             _phantom: PhantomData,
         }
     }
@@ -157,8 +354,15 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * Technically, this should be called the _frame rate_ or _frames per second_,
      * because it refers to the number of complete frames transferred per second.
      * But it is traditionally called _sample rate_. Se we use that term.
+     *
+     * The rates out of range from 8000 to 384000 Hz fail to open on any device, so they are
+     * reported by [`AudioStreamBuilder::validate`], and logged with `log` feature.
      */
     pub fn set_sample_rate(mut self, sample_rate: i32) -> Self {
+        #[cfg(feature = "log")]
+        if sample_rate > 0 && !super::SAMPLE_RATE_RANGE.contains(&sample_rate) {
+            log::warn!("{}", ConfigWarning::SampleRateOutOfRange(sample_rate));
+        }
         self._raw_base_mut().mSampleRate = sample_rate;
         self
     }
@@ -169,6 +373,10 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * Default is kUnspecified. If the value is unspecified then
      * the actual number may vary from callback to callback.
      *
+     * Note that kUnspecified is `0` in Oboe, so passing `0` is the same as
+     * [`AudioStreamBuilder::set_frames_per_callback_unspecified`]. Negative values are invalid
+     * and make opening the stream fail.
+     *
      * If an application can handle a varying number of frames then we recommend
      * leaving this unspecified. This allow the underlying API to optimize
      * the callbacks. But if your application is, for example, doing FFTs or other block
@@ -179,6 +387,15 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
         self
     }
 
+    /**
+     * Let the underlying API choose the number of frames for each data callback.
+     *
+     * This is the default. The number of frames may vary from callback to callback.
+     */
+    pub fn set_frames_per_callback_unspecified(self) -> Self {
+        self.set_frames_per_callback(0)
+    }
+
     /**
      * Request a sample data format, for example `f32`.
      *
@@ -210,6 +427,95 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      */
     pub fn set_buffer_capacity_in_frames(mut self, buffer_capacity_in_frames: i32) -> Self {
         self._raw_base_mut().mBufferCapacityInFrames = buffer_capacity_in_frames;
+        self.bursts.capacity = 0;
+        self
+    }
+
+    /**
+     * Set the buffer size in bursts, which is applied after the stream is opened.
+     *
+     * The burst is not known until the stream is opened, so the multiplier is recorded and
+     * the buffer size is set to `bursts` times the actual frames per burst right after opening,
+     * but not above the buffer capacity.
+     *
+     * The capacity cannot change after opening, so it is requested in terms of the default
+     * frames per burst (see [`DefaultStreamValues`]) when it is known, otherwise the capacity is
+     * left unspecified. It overrides any absolute capacity set by
+     * [`AudioStreamBuilder::set_buffer_capacity_in_frames`]. Zero bursts clears the setting.
+     */
+    pub fn set_buffer_capacity_bursts(mut self, bursts: u32) -> Self {
+        let frames_per_burst = DefaultStreamValues::get_frames_per_burst().max(0);
+        self._raw_base_mut().mBufferCapacityInFrames =
+            (bursts.min(i32::MAX as u32) as i32).saturating_mul(frames_per_burst);
+        self.bursts.capacity = bursts;
+        self
+    }
+
+    /**
+     * Set the desired latency in bursts, which is applied after the stream is opened.
+     *
+     * Right after opening, the buffer size is set to `bursts` times the actual frames per burst,
+     * but not above the buffer capacity. Unlike [`LatencyClass`](crate::LatencyClass), which is applied to an opened
+     * stream, it is the exact number of bursts and survives the reopening of stream.
+     *
+     * It also requests the capacity of at least `bursts` default bursts
+     * (see [`DefaultStreamValues`]), so the capacity set by
+     * [`AudioStreamBuilder::set_buffer_capacity_in_frames`] or
+     * [`AudioStreamBuilder::set_buffer_capacity_bursts`] is kept when it is larger.
+     * The buffer size in bursts set here takes precedence over the capacity in bursts.
+     * Setting the capacity after this call overrides the requested capacity only,
+     * the buffer size is still applied. Zero bursts clears the setting.
+     */
+    pub fn set_desired_latency_bursts(mut self, bursts: u32) -> Self {
+        let frames_per_burst = DefaultStreamValues::get_frames_per_burst().max(0);
+        let frames = (bursts.min(i32::MAX as u32) as i32).saturating_mul(frames_per_burst);
+        let capacity = &mut self._raw_base_mut().mBufferCapacityInFrames;
+        *capacity = (*capacity).max(frames);
+        self.bursts.latency = bursts;
+        self
+    }
+
+    /**
+     * Apply the recipe of the lowest latency in one call.
+     *
+     * It requests AAudio with the exclusive sharing mode and the low latency performance mode,
+     * float samples at the native sample rate without the resampling in Oboe, and the buffer
+     * of 2 bursts, see [`AudioStreamBuilder::set_desired_latency_bursts`].
+     *
+     * The system may grant less, e.g. the shared mode when the MMAP path is not available,
+     * so check the opened stream using [`LowLatencyOutcome::new`] and decide whether
+     * the compromise is acceptable.
+     */
+    pub fn request_lowest_latency(self) -> AudioStreamBuilder<D, C, f32> {
+        self.set_audio_api(AudioApi::AAudio)
+            .set_exclusive()
+            .set_performance_mode(PerformanceMode::LowLatency)
+            .set_sample_rate(0)
+            .set_sample_rate_conversion_quality(SampleRateConversionQuality::None)
+            .set_desired_latency_bursts(2)
+            .set_f32()
+    }
+
+    /**
+     * Request the number of buffers in the OpenSL ES buffer queue.
+     *
+     * The OpenSL ES stream derives the queue length from the requested buffer capacity
+     * and the default frames per burst (see [`DefaultStreamValues`]), so this sets
+     * the buffer capacity to `queue_length` bursts. Oboe keeps at least 2 and at most 8 buffers.
+     *
+     * It does not apply to AAudio, so when AAudio will be used the builder is left
+     * unchanged and a warning is logged with `log` feature.
+     */
+    pub fn set_opensl_buffer_queue_length(mut self, queue_length: u32) -> Self {
+        if self.will_use_aaudio() {
+            #[cfg(feature = "log")]
+            log::warn!("The OpenSL ES buffer queue length is ignored when AAudio is used");
+        } else {
+            let frames_per_burst = DefaultStreamValues::get_frames_per_burst().max(1);
+            self._raw_base_mut().mBufferCapacityInFrames =
+                (queue_length as i32).saturating_mul(frames_per_burst);
+            self.bursts.capacity = 0;
+        }
         self
     }
 
@@ -222,8 +528,10 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * returns true. Otherwise __OpenSL ES__ will be used.
      */
     pub fn get_audio_api(&self) -> AudioApi {
-        FromPrimitive::from_i32(unsafe { ffi::oboe_AudioStreamBuilder_getAudioApi(&*self.raw) })
-            .unwrap()
+        FromPrimitive::from_i32(unsafe {
+            ffi_call!(oboe_AudioStreamBuilder_getAudioApi(&*self.raw))
+        })
+        .unwrap()
     }
 
     /**
@@ -235,9 +543,17 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * Specifying __OpenSL ES__ should mainly be used to test legacy performance/functionality.
      *
      * If the caller requests AAudio and it is supported then AAudio will be used.
+     *
+     * The [`AudioApi`] can express only the values which is permitted by Oboe,
+     * so the invalid API can't be requested and the value is passed as is.
      */
     pub fn set_audio_api(mut self, audio_api: AudioApi) -> Self {
-        unsafe { ffi::oboe_AudioStreamBuilder_setAudioApi(&mut *self.raw, audio_api as i32) }
+        unsafe {
+            ffi_call!(oboe_AudioStreamBuilder_setAudioApi(
+                &mut *self.raw,
+                audio_api as i32
+            ))
+        }
         self
     }
 
@@ -247,7 +563,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * AAudio was introduced in the Oreo 8.0 release.
      */
     pub fn is_aaudio_supported() -> bool {
-        unsafe { ffi::oboe_AudioStreamBuilder_isAAudioSupported() }
+        unsafe { ffi_call!(oboe_AudioStreamBuilder_isAAudioSupported()) }
     }
 
     /**
@@ -257,7 +573,7 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
      * AAudio is not recommended for Android 8.0 or earlier versions.
      */
     pub fn is_aaudio_recommended() -> bool {
-        unsafe { ffi::oboe_AudioStreamBuilder_isAAudioRecommended() }
+        unsafe { ffi_call!(oboe_AudioStreamBuilder_isAAudioRecommended()) }
     }
 
     /**
@@ -444,20 +760,154 @@ impl<D, C, T> AudioStreamBuilder<D, C, T> {
         (audio_api == AudioApi::AAudio && Self::is_aaudio_supported())
             || (audio_api == AudioApi::Unspecified && Self::is_aaudio_recommended())
     }
+
+    /**
+     * Check the settings for known contradictory combinations before opening the stream.
+     *
+     * It catches logic errors in configuration, e.g. the exclusive mode without the low latency
+     * performance mode, but not the device-specific failures. The audio API is resolved
+     * like on opening, see [`AudioStreamBuilder::will_use_aaudio`].
+     */
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigWarning>> {
+        let audio_api = if self.will_use_aaudio() {
+            AudioApi::AAudio
+        } else {
+            AudioApi::OpenSLES
+        };
+        let warnings = validate_config(self, audio_api);
+        if warnings.is_empty() {
+            Ok(())
+        } else {
+            Err(warnings)
+        }
+    }
+
+    /**
+     * Check that the builders have the same settings except the audio API.
+     *
+     * It is useful to ensure that two builders differ only in the requested API,
+     * e.g. when comparing AAudio and OpenSL ES on the same device.
+     */
+    pub fn eq_config<D1, C1, T1>(&self, other: &AudioStreamBuilder<D1, C1, T1>) -> bool {
+        let a = self._raw_base();
+        let b = other._raw_base();
+
+        a.mFramesPerCallback == b.mFramesPerCallback
+            && a.mChannelCount == b.mChannelCount
+            && a.mSampleRate == b.mSampleRate
+            && a.mDeviceId == b.mDeviceId
+            && a.mBufferCapacityInFrames == b.mBufferCapacityInFrames
+            && a.mSharingMode == b.mSharingMode
+            && a.mFormat == b.mFormat
+            && a.mDirection == b.mDirection
+            && a.mPerformanceMode == b.mPerformanceMode
+            && a.mUsage == b.mUsage
+            && a.mContentType == b.mContentType
+            && a.mInputPreset == b.mInputPreset
+            && a.mSessionId == b.mSessionId
+            && a.mChannelConversionAllowed == b.mChannelConversionAllowed
+            && a.mFormatConversionAllowed == b.mFormatConversionAllowed
+            && a.mSampleRateConversionQuality == b.mSampleRateConversionQuality
+            && self.bursts == other.bursts
+    }
+
+    /**
+     * Get the settings which differ from the Oboe defaults.
+     *
+     * Returns the pairs of setting name and value, e.g.
+     * `[("sample_rate", "48000"), ("performance_mode", "LowLatency")]`,
+     * which is useful for compact logging. The values are formatted as in the [`fmt::Debug`]
+     * output of builder, and the deferred buffer settings in bursts are included too.
+     */
+    pub fn non_default_settings(&self) -> Vec<(&'static str, String)> {
+        let default = AudioStreamBuilder::default();
+        let mut settings = Vec::new();
+
+        macro_rules! settings {
+            ($($name:literal: |$builder:ident| $value:expr => $format:literal,)*) => {
+                $({
+                    let value = { let $builder = self; $value };
+                    if value != { let $builder = &default; $value } {
+                        settings.push(($name, format!($format, value)));
+                    }
+                })*
+            };
+        }
+
+        settings! {
+            "audio_api": |b| b.get_audio_api() => "{:?}",
+            "direction": |b| b.get_direction() => "{:?}",
+            "device_id": |b| b.get_device_id() => "{}",
+            "session_id": |b| b.get_session_id() => "{:?}",
+            "sample_rate": |b| b.get_sample_rate() => "{}",
+            "sample_rate_conversion_quality": |b| b.get_sample_rate_conversion_quality() => "{:?}",
+            "channel_count": |b| b.get_channel_count() => "{:?}",
+            "channel_conversion_allowed": |b| b.is_channel_conversion_allowed() => "{}",
+            "format": |b| b.get_format() => "{:?}",
+            "format_conversion_allowed": |b| b.is_format_conversion_allowed() => "{}",
+            "frames_per_callback": |b| b.get_frames_per_callback() => "{}",
+            "buffer_capacity_in_frames": |b| b.get_buffer_capacity_in_frames() => "{}",
+            "buffer_capacity_bursts": |b| b.bursts.capacity => "{}",
+            "desired_latency_bursts": |b| b.bursts.latency => "{}",
+            "sharing_mode": |b| b.get_sharing_mode() => "{:?}",
+            "performance_mode": |b| b.get_performance_mode() => "{:?}",
+            "usage": |b| b.get_usage() => "{:?}",
+            "content_type": |b| b.get_content_type() => "{:?}",
+            "input_preset": |b| b.get_input_preset() => "{:?}",
+        }
+
+        settings
+    }
 }
 
 impl<D: IsDirection, C: IsChannelCount, T: IsFormat> AudioStreamBuilder<D, C, T> {
     /**
      * Create and open a synchronous (blocking) stream based on the current settings.
+     *
+     * When the device cannot provide more streams with requested settings, the
+     * [`Error::NoFreeHandles`] or [`Error::Unavailable`] is returned. In that case retrying is
+     * pointless, so consider falling back to a shared stream, or see
+     * [`AudioStreamBuilder::max_low_latency_streams`].
      */
     pub fn open_stream(self) -> Result<AudioStreamSync<D, (T, C)>> {
         let mut stream = MaybeUninit::<*mut ffi::oboe_AudioStream>::uninit();
-        let Self { mut raw, .. } = self;
+        let Self {
+            mut raw, bursts, ..
+        } = self;
 
         wrap_status(unsafe {
-            ffi::oboe_AudioStreamBuilder_openStream(&mut *raw, stream.as_mut_ptr())
+            ffi_call!(oboe_AudioStreamBuilder_openStream(
+                &mut *raw,
+                stream.as_mut_ptr()
+            ))
         })
         .map(|_| AudioStreamSync::wrap_raw(unsafe { stream.assume_init() }))
+        .and_then(|mut stream| bursts.apply(&mut stream).map(|_| stream))
+    }
+
+    /**
+     * Create and open a synchronous (blocking) stream based on the current settings.
+     *
+     * Also returns the outcome of negotiation which contains requested and granted parameters.
+     */
+    #[allow(clippy::type_complexity)]
+    pub fn open_stream_detailed(self) -> Result<(AudioStreamSync<D, (T, C)>, OpenOutcome)> {
+        let requested = StreamParameters::new(&self, self.get_audio_api());
+
+        self.open_stream().map(|stream| {
+            let outcome = OpenOutcome::new(requested, &stream);
+            (stream, outcome)
+        })
+    }
+
+    /**
+     * Open the synchronous stream and start it in one call
+     *
+     * Returns the guard which stops the stream on drop, see [`StartedStream`].
+     * When the stream is opened but fails to start, it is closed before the error is returned.
+     */
+    pub fn open_and_start(self) -> Result<StartedStream<AudioStreamSync<D, (T, C)>>> {
+        self.open_stream().and_then(AudioStream::started)
     }
 }
 
@@ -479,22 +929,78 @@ impl<C: IsChannelCount, T: IsFormat> AudioStreamBuilder<Input, C, T> {
      *
      * This leaves a raw pointer as the logical type choice. The only caveat being that the caller must not destroy
      * the callback before the stream has been closed.
+     *
+     * The state of callback is allocated here, see [`AudioStreamBuilderAsync`] for the
+     * allocations which are made for the callback.
      */
     pub fn set_callback<F>(self, stream_callback: F) -> AudioStreamBuilderAsync<Input, F>
     where
         F: AudioInputCallback<FrameType = (T, C)>,
         (T, C): IsFrameType,
     {
-        let mut callback = AudioCallbackWrapper::<Input, F>::wrap(stream_callback);
-        let Self { mut raw, .. } = self;
-        unsafe {
-            ffi::oboe_AudioStreamBuilder_setCallback(&mut *raw, callback.raw_callback());
-        }
-        AudioStreamBuilderAsync {
-            raw,
-            callback,
-            _phantom: PhantomData,
-        }
+        self.set_callback_wrapper(AudioCallbackWrapper::<Input, F>::wrap(stream_callback))
+    }
+
+    /**
+     * Use the closure which processes captured frames as a data callback.
+     *
+     * This is a shortcut for [`AudioStreamBuilder::set_callback`] with [`AudioInputFn`] adapter
+     * which allows skipping the callback trait implementation. The frame type is inferred
+     * from the format and channel count of the builder.
+     */
+    pub fn set_input_fn<F>(
+        self,
+        closure: F,
+    ) -> AudioStreamBuilderAsync<Input, AudioInputFn<(T, C), F>>
+    where
+        F: FnMut(&[<(T, C) as IsFrameType>::Type]) -> DataCallbackResult,
+        (T, C): IsFrameType,
+    {
+        self.set_callback(AudioInputFn::new(closure))
+    }
+
+    /**
+     * Use the boxed trait object as a data callback.
+     *
+     * This allows choosing the callback at runtime, e.g. among several generators of the
+     * same frame type. Each callback is dispatched through the vtable, which costs an indirect
+     * call per buffer and prevents inlining the callback into the wrapper, which is negligible
+     * unless the buffers are very small.
+     */
+    pub fn set_boxed_callback(
+        self,
+        stream_callback: Box<dyn AudioInputCallback<FrameType = (T, C)> + Send>,
+    ) -> AudioStreamBuilderAsync<Input, Box<dyn AudioInputCallback<FrameType = (T, C)> + Send>>
+    where
+        (T, C): IsFrameType,
+    {
+        self.set_callback(stream_callback)
+    }
+}
+
+impl<C: IsChannelCount> AudioStreamBuilder<Input, C, f32> {
+    /**
+     * Use the callback which processes float frames with the format conversion in software.
+     *
+     * The stream is opened with unspecified format, so it gets the native format of device,
+     * and the format conversion of Oboe is disabled. The frames are converted from or to the
+     * negotiated format by the [`SoftwareConversion`] adapter with the chosen quality,
+     * see [`ConversionQuality`]. Query the format of opened stream to get the native one.
+     */
+    pub fn set_software_format_conversion<F, X>(
+        self,
+        quality: ConversionQuality,
+        stream_callback: F,
+    ) -> AudioStreamBuilderAsync<Input, SoftwareConversion<F, X>>
+    where
+        F: AudioInputCallback<FrameType = (f32, C)>,
+        (f32, C): IsFrameType<Type = X>,
+        X: IsFrame<Sample = f32>,
+    {
+        let callback = SoftwareConversion::new(stream_callback, quality);
+        self.set_format::<Unspecified>()
+            .set_format_conversion_allowed(false)
+            .set_callback_wrapper(AudioCallbackWrapper::<Input, _>::wrap_native(callback))
     }
 }
 
@@ -516,31 +1022,101 @@ impl<C: IsChannelCount, T: IsFormat> AudioStreamBuilder<Output, C, T> {
      *
      * This leaves a raw pointer as the logical type choice. The only caveat being that the caller must not destroy
      * the callback before the stream has been closed.
+     *
+     * The state of callback is allocated here, see [`AudioStreamBuilderAsync`] for the
+     * allocations which are made for the callback.
      */
     pub fn set_callback<F>(self, stream_callback: F) -> AudioStreamBuilderAsync<Output, F>
     where
         F: AudioOutputCallback<FrameType = (T, C)>,
         (T, C): IsFrameType,
     {
-        let mut callback = AudioCallbackWrapper::<Output, F>::wrap(stream_callback);
-        let Self { mut raw, .. } = self;
-        unsafe {
-            ffi::oboe_AudioStreamBuilder_setCallback(&mut *raw, callback.raw_callback());
-        }
-        AudioStreamBuilderAsync {
-            raw,
-            callback,
-            _phantom: PhantomData,
-        }
+        self.set_callback_wrapper(AudioCallbackWrapper::<Output, F>::wrap(stream_callback))
+    }
+
+    /**
+     * Use the closure which renders output frames as a data callback.
+     *
+     * This is a shortcut for [`AudioStreamBuilder::set_callback`] with [`AudioOutputFn`] adapter
+     * which allows skipping the callback trait implementation. The frame type is inferred
+     * from the format and channel count of the builder.
+     */
+    pub fn set_output_fn<F>(
+        self,
+        closure: F,
+    ) -> AudioStreamBuilderAsync<Output, AudioOutputFn<(T, C), F>>
+    where
+        F: FnMut(&mut [<(T, C) as IsFrameType>::Type]) -> DataCallbackResult,
+        (T, C): IsFrameType,
+    {
+        self.set_callback(AudioOutputFn::new(closure))
+    }
+
+    /**
+     * Use the boxed trait object as a data callback.
+     *
+     * This allows choosing the callback at runtime, e.g. among several generators of the
+     * same frame type. Each callback is dispatched through the vtable, which costs an indirect
+     * call per buffer and prevents inlining the callback into the wrapper, which is negligible
+     * unless the buffers are very small.
+     */
+    pub fn set_boxed_callback(
+        self,
+        stream_callback: Box<dyn AudioOutputCallback<FrameType = (T, C)> + Send>,
+    ) -> AudioStreamBuilderAsync<Output, Box<dyn AudioOutputCallback<FrameType = (T, C)> + Send>>
+    where
+        (T, C): IsFrameType,
+    {
+        self.set_callback(stream_callback)
+    }
+}
+
+impl<C: IsChannelCount> AudioStreamBuilder<Output, C, f32> {
+    /**
+     * Use the callback which processes float frames with the format conversion in software.
+     *
+     * The stream is opened with unspecified format, so it gets the native format of device,
+     * and the format conversion of Oboe is disabled. The frames are converted from or to the
+     * negotiated format by the [`SoftwareConversion`] adapter with the chosen quality,
+     * see [`ConversionQuality`]. Query the format of opened stream to get the native one.
+     */
+    pub fn set_software_format_conversion<F, X>(
+        self,
+        quality: ConversionQuality,
+        stream_callback: F,
+    ) -> AudioStreamBuilderAsync<Output, SoftwareConversion<F, X>>
+    where
+        F: AudioOutputCallback<FrameType = (f32, C)>,
+        (f32, C): IsFrameType<Type = X>,
+        X: IsFrame<Sample = f32>,
+    {
+        let callback = SoftwareConversion::new(stream_callback, quality);
+        self.set_format::<Unspecified>()
+            .set_format_conversion_allowed(false)
+            .set_callback_wrapper(AudioCallbackWrapper::<Output, _>::wrap_native(callback))
     }
 }
 
 /**
  * Factory for an audio stream.
+ *
+ * The state of callback is allocated when the callback is set, before the stream is opened:
+ * the callback is boxed using the global allocator, the panic, error and performance hint
+ * slots are shared with the stream using three `Arc`s, and the C++ wrapper which dispatches
+ * to the callback is allocated by Oboe.
+ *
+ * The wrapper does not allocate in the data and error callbacks, except that the performance
+ * hint session is created on the first data callback after
+ * [`AudioStreamAsync::enable_performance_hints`](crate::AudioStreamAsync::enable_performance_hints).
+ * The adapters of this crate allocate their buffers before the callbacks run, e.g.
+ * [`SoftwareConversion`] when the stream is opened and
+ * [`FullDuplexStream`](crate::FullDuplexStream) when it is created. So, apart from the hints,
+ * preallocating the state of the callback itself keeps the audio loop free of allocations.
  */
 pub struct AudioStreamBuilderAsync<D, F> {
     raw: AudioStreamBuilderHandle,
     callback: AudioCallbackWrapper<D, F>,
+    bursts: DeferredBursts,
     _phantom: PhantomData<(D, F)>,
 }
 
@@ -560,20 +1136,81 @@ impl<D, F> RawAudioStreamBase for AudioStreamBuilderAsync<D, F> {
     }
 }
 
+impl<D, F> AudioStreamBuilderAsync<D, F> {
+    /**
+     * Get the audio API which will be requested when opening the stream.
+     *
+     * See [`AudioStreamBuilder::get_audio_api`].
+     */
+    pub fn get_audio_api(&self) -> AudioApi {
+        FromPrimitive::from_i32(unsafe {
+            ffi_call!(oboe_AudioStreamBuilder_getAudioApi(&*self.raw))
+        })
+        .unwrap()
+    }
+}
+
 impl<F: AudioInputCallback + Send> AudioStreamBuilderAsync<Input, F> {
+    /**
+     * Drop the first captured frames instead of passing them to the callback.
+     *
+     * The first buffers after starting the input stream often contain stale data or the
+     * transient of the microphone, which is audible as a pop at the start of recording.
+     * Typically 20-50 ms is enough, e.g. 2400 frames at 48000 Hz.
+     *
+     * The counting starts when the stream is opened or reconnected, and applies only
+     * to the input direction. Zero frames disables it.
+     */
+    pub fn set_discard_initial_frames(mut self, frames: u32) -> Self {
+        self.callback.set_discard_initial_frames(frames as usize);
+        self
+    }
+
     /**
      * Create and open an asynchronous (callback-driven) input stream based on the current settings.
      */
     pub fn open_stream(self) -> Result<AudioStreamAsync<Input, F>> {
         let mut stream = MaybeUninit::<*mut ffi::oboe_AudioStream>::uninit();
+        let config = StreamConfig::new(&self, self.get_audio_api());
         let Self {
-            mut raw, callback, ..
+            mut raw,
+            callback,
+            bursts,
+            ..
         } = self;
 
         wrap_status(unsafe {
-            ffi::oboe_AudioStreamBuilder_openStream(&mut *raw, stream.as_mut_ptr())
+            ffi_call!(oboe_AudioStreamBuilder_openStream(
+                &mut *raw,
+                stream.as_mut_ptr()
+            ))
         })
-        .map(|_| AudioStreamAsync::wrap_raw(unsafe { stream.assume_init() }, callback))
+        .map(|_| AudioStreamAsync::wrap_raw(unsafe { stream.assume_init() }, callback, config))
+        .and_then(|mut stream| bursts.apply(&mut stream).map(|_| stream))
+    }
+
+    /**
+     * Create and open an asynchronous (callback-driven) input stream based on the current settings.
+     *
+     * Also returns the outcome of negotiation which contains requested and granted parameters.
+     */
+    pub fn open_stream_detailed(self) -> Result<(AudioStreamAsync<Input, F>, OpenOutcome)> {
+        let requested = StreamParameters::new(&self, self.get_audio_api());
+
+        self.open_stream().map(|stream| {
+            let outcome = OpenOutcome::new(requested, &stream);
+            (stream, outcome)
+        })
+    }
+
+    /**
+     * Open the asynchronous input stream and start it in one call
+     *
+     * Returns the guard which stops the stream on drop, see [`StartedStream`].
+     * When the stream is opened but fails to start, it is closed before the error is returned.
+     */
+    pub fn open_and_start(self) -> Result<StartedStream<AudioStreamAsync<Input, F>>> {
+        self.open_stream().and_then(AudioStream::started)
     }
 }
 
@@ -583,13 +1220,74 @@ impl<F: AudioOutputCallback + Send> AudioStreamBuilderAsync<Output, F> {
      */
     pub fn open_stream(self) -> Result<AudioStreamAsync<Output, F>> {
         let mut stream = MaybeUninit::<*mut ffi::oboe_AudioStream>::uninit();
+        let config = StreamConfig::new(&self, self.get_audio_api());
         let Self {
-            mut raw, callback, ..
+            mut raw,
+            callback,
+            bursts,
+            ..
         } = self;
 
         wrap_status(unsafe {
-            ffi::oboe_AudioStreamBuilder_openStream(&mut *raw, stream.as_mut_ptr())
+            ffi_call!(oboe_AudioStreamBuilder_openStream(
+                &mut *raw,
+                stream.as_mut_ptr()
+            ))
         })
-        .map(|_| AudioStreamAsync::wrap_raw(unsafe { stream.assume_init() }, callback))
+        .map(|_| AudioStreamAsync::wrap_raw(unsafe { stream.assume_init() }, callback, config))
+        .and_then(|mut stream| bursts.apply(&mut stream).map(|_| stream))
+    }
+
+    /**
+     * Create and open an asynchronous (callback-driven) output stream based on the current settings.
+     *
+     * Also returns the outcome of negotiation which contains requested and granted parameters.
+     */
+    pub fn open_stream_detailed(self) -> Result<(AudioStreamAsync<Output, F>, OpenOutcome)> {
+        let requested = StreamParameters::new(&self, self.get_audio_api());
+
+        self.open_stream().map(|stream| {
+            let outcome = OpenOutcome::new(requested, &stream);
+            (stream, outcome)
+        })
+    }
+
+    /**
+     * Open the asynchronous output stream and start it in one call
+     *
+     * Returns the guard which stops the stream on drop, see [`StartedStream`].
+     * When the stream is opened but fails to start, it is closed before the error is returned.
+     */
+    pub fn open_and_start(self) -> Result<StartedStream<AudioStreamAsync<Output, F>>> {
+        self.open_stream().and_then(AudioStream::started)
+    }
+}
+
+/// The buffer settings in bursts which are deferred until the stream is opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct DeferredBursts {
+    capacity: u32,
+    latency: u32,
+}
+
+impl DeferredBursts {
+    fn apply<S: AudioStreamSafe>(self, stream: &mut S) -> Status {
+        // The explicit latency takes precedence over the capacity
+        let bursts = if self.latency > 0 {
+            self.latency
+        } else {
+            self.capacity
+        };
+        if bursts > 0 {
+            let frames = (stream.get_frames_per_burst().max(1) as i64 * bursts as i64)
+                .min(i32::MAX as i64) as i32;
+            let capacity = stream.get_buffer_capacity_in_frames();
+            stream.set_buffer_size_in_frames(if capacity > 0 {
+                frames.min(capacity)
+            } else {
+                frames
+            })?;
+        }
+        Ok(())
     }
 }
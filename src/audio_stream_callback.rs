@@ -1,17 +1,22 @@
 use std::{
+    any::Any,
     ffi::c_void,
     marker::PhantomData,
     ops::{Deref, DerefMut},
+    panic::{catch_unwind, AssertUnwindSafe},
     slice::{from_raw_parts, from_raw_parts_mut},
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicI64, Ordering},
+        Arc, Mutex,
+    },
 };
 
 use oboe_sys as ffi;
 
-use num_traits::FromPrimitive;
-
 use super::{
-    AudioInputStreamSafe, AudioOutputStreamSafe, AudioStreamRef, DataCallbackResult, Error, Input,
-    IsFrameType, Output,
+    ffi_call, AudioInputStreamSafe, AudioOutputStreamSafe, AudioStreamRef, AudioStreamSafe,
+    DataCallbackResult, Error, Input, IsFrameType, Output, PerformanceHintReporter,
+    PerformanceHintTarget,
 };
 
 /**
@@ -60,14 +65,29 @@ pub trait AudioInputCallback {
      *
      * This callback could be used to reopen a new stream on another device.
      * You can safely delete the old AudioStream in this method.
+     *
+     * With `log` feature the default implementation logs the error and the stream parameters.
      */
     fn on_error_after_close(
         &mut self,
         _audio_stream: &mut dyn AudioInputStreamSafe,
         _error: Error,
     ) {
+        #[cfg(feature = "log")]
+        log_error_after_close(&*_audio_stream, _error);
     }
 
+    /**
+     * This will be called before `on_audio_ready` when the number of xruns increased.
+     *
+     * The `delta` is the number of xruns since the previous call. It requires the support of
+     * xrun counting, see
+     * [`AudioStreamSafe::is_xrun_count_supported`](crate::AudioStreamSafe::is_xrun_count_supported).
+     *
+     * It is called from the real-time thread, so the same restrictions as in `on_audio_ready` apply.
+     */
+    fn on_xrun(&mut self, _audio_stream: &mut dyn AudioInputStreamSafe, _delta: i32) {}
+
     /**
      * A buffer is ready for processing.
      *
@@ -157,14 +177,29 @@ pub trait AudioOutputCallback {
      *
      * This callback could be used to reopen a new stream on another device.
      * You can safely delete the old AudioStream in this method.
+     *
+     * With `log` feature the default implementation logs the error and the stream parameters.
      */
     fn on_error_after_close(
         &mut self,
         _audio_stream: &mut dyn AudioOutputStreamSafe,
         _error: Error,
     ) {
+        #[cfg(feature = "log")]
+        log_error_after_close(&*_audio_stream, _error);
     }
 
+    /**
+     * This will be called before `on_audio_ready` when the number of xruns increased.
+     *
+     * The `delta` is the number of xruns since the previous call. It requires the support of
+     * xrun counting, see
+     * [`AudioStreamSafe::is_xrun_count_supported`](crate::AudioStreamSafe::is_xrun_count_supported).
+     *
+     * It is called from the real-time thread, so the same restrictions as in `on_audio_ready` apply.
+     */
+    fn on_xrun(&mut self, _audio_stream: &mut dyn AudioOutputStreamSafe, _delta: i32) {}
+
     /**
      * A buffer is ready for processing.
      *
@@ -208,6 +243,439 @@ pub trait AudioOutputCallback {
     ) -> DataCallbackResult;
 }
 
+/// The input callback which handles the frames in the format negotiated by stream
+///
+/// It is used when the stream is opened with unspecified format, so the format of frames
+/// is known only at runtime, see `SoftwareConversion`.
+pub(crate) trait NativeInputCallback: AudioInputCallback {
+    /// Process `num_frames` frames in the format and the channel count of `audio_stream`
+    ///
+    /// The `audio_data` must point to the whole frames.
+    unsafe fn on_native_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: *const c_void,
+        num_frames: usize,
+    ) -> DataCallbackResult;
+
+    /// Preallocate the buffers for the callbacks of up to `frames` frames
+    ///
+    /// It is called when the stream is opened, before the callbacks run.
+    fn reserve_frames(&mut self, frames: usize);
+}
+
+/// The output callback which handles the frames in the format negotiated by stream
+///
+/// See [`NativeInputCallback`].
+pub(crate) trait NativeOutputCallback: AudioOutputCallback {
+    /// Render `num_frames` frames in the format and the channel count of `audio_stream`
+    ///
+    /// The `audio_data` must point to the whole frames.
+    unsafe fn on_native_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: *mut c_void,
+        num_frames: usize,
+    ) -> DataCallbackResult;
+
+    /// Preallocate the buffers for the callbacks of up to `frames` frames
+    ///
+    /// It is called when the stream is opened, before the callbacks run.
+    fn reserve_frames(&mut self, frames: usize);
+}
+
+/**
+ * The callback state which can be cleared between playback sessions
+ *
+ * It is opt-in: implement it for the callback to use
+ * [`AudioStreamAsync::stop_and_reset`](crate::AudioStreamAsync::stop_and_reset).
+ */
+pub trait Resettable {
+    /**
+     * Clear the accumulated state, e.g. statistics or buffered frames
+     *
+     * It is called only when the stream is stopped, so the data callback does not run concurrently.
+     */
+    fn reset(&mut self);
+}
+
+/**
+ * The adapter which allows to use a closure as an input callback
+ *
+ * See [`AudioStreamBuilder::set_input_fn`](crate::AudioStreamBuilder::set_input_fn).
+ */
+pub struct AudioInputFn<T, F> {
+    closure: F,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, F> AudioInputFn<T, F>
+where
+    T: IsFrameType,
+    F: FnMut(&[T::Type]) -> DataCallbackResult,
+{
+    /**
+     * Wrap the closure which processes input frames
+     */
+    pub fn new(closure: F) -> Self {
+        Self {
+            closure,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, F> AudioInputCallback for AudioInputFn<T, F>
+where
+    T: IsFrameType,
+    F: FnMut(&[T::Type]) -> DataCallbackResult,
+{
+    type FrameType = T;
+
+    fn on_audio_ready(
+        &mut self,
+        _audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: &[T::Type],
+    ) -> DataCallbackResult {
+        (self.closure)(audio_data)
+    }
+}
+
+/**
+ * The adapter which allows to use a closure as an output callback
+ *
+ * See [`AudioStreamBuilder::set_output_fn`](crate::AudioStreamBuilder::set_output_fn).
+ */
+pub struct AudioOutputFn<T, F> {
+    closure: F,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, F> AudioOutputFn<T, F>
+where
+    T: IsFrameType,
+    F: FnMut(&mut [T::Type]) -> DataCallbackResult,
+{
+    /**
+     * Wrap the closure which renders output frames
+     */
+    pub fn new(closure: F) -> Self {
+        Self {
+            closure,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, F> AudioOutputCallback for AudioOutputFn<T, F>
+where
+    T: IsFrameType,
+    F: FnMut(&mut [T::Type]) -> DataCallbackResult,
+{
+    type FrameType = T;
+
+    fn on_audio_ready(
+        &mut self,
+        _audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [T::Type],
+    ) -> DataCallbackResult {
+        (self.closure)(audio_data)
+    }
+}
+
+/// Log the error which closed the stream
+#[cfg(feature = "log")]
+fn log_error_after_close<S: AudioStreamSafe + ?Sized>(audio_stream: &S, error: Error) {
+    log::error!(
+        "{:?} stream closed after error: {} (api: {:?}, device: {}, sample rate: {}, channels: {:?}, format: {:?}, sharing: {:?}, performance: {:?})",
+        audio_stream.get_direction(),
+        error,
+        audio_stream.get_audio_api(),
+        audio_stream.get_device_id(),
+        audio_stream.get_sample_rate(),
+        audio_stream.get_channel_count(),
+        audio_stream.get_format(),
+        audio_stream.get_sharing_mode(),
+        audio_stream.get_performance_mode(),
+    );
+}
+
+impl<T: AudioInputCallback + ?Sized> AudioInputCallback for Box<T> {
+    type FrameType = T::FrameType;
+
+    fn on_error_before_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        (**self).on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        (**self).on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, delta: i32) {
+        (**self).on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: &[<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        (**self).on_audio_ready(audio_stream, audio_data)
+    }
+}
+
+impl<T: AudioOutputCallback + ?Sized> AudioOutputCallback for Box<T> {
+    type FrameType = T::FrameType;
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        (**self).on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        (**self).on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        (**self).on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        (**self).on_audio_ready(audio_stream, audio_data)
+    }
+}
+
+/**
+ * Implement [`AudioInputCallback`] for the type with the frame type declared once
+ *
+ * The type of `data` is derived from the frame type, so the frame type of callback cannot
+ * disagree with the data it processes. When the frame type does not match the builder,
+ * [`AudioStreamBuilder::set_callback`](crate::AudioStreamBuilder::set_callback) reports
+ * the expected and found frame types.
+ *
+ * ```ignore
+ * struct Meter { peak: f32 }
+ *
+ * oboe::impl_input_callback!(Meter, (f32, Mono), |this, _stream, data| {
+ *     this.peak = data.iter().fold(this.peak, |peak, sample| peak.max(sample.abs()));
+ *     DataCallbackResult::Continue
+ * });
+ * ```
+ */
+#[macro_export]
+macro_rules! impl_input_callback {
+    ($type:ty, $frame:ty, |$this:ident, $stream:ident, $data:ident| $body:expr) => {
+        impl $crate::AudioInputCallback for $type {
+            type FrameType = $frame;
+
+            fn on_audio_ready(
+                &mut self,
+                $stream: &mut dyn $crate::AudioInputStreamSafe,
+                $data: &[<$frame as $crate::IsFrameType>::Type],
+            ) -> $crate::DataCallbackResult {
+                let $this = self;
+                $body
+            }
+        }
+    };
+}
+
+/**
+ * Implement [`AudioOutputCallback`] for the type with the frame type declared once
+ *
+ * See [`impl_input_callback`](crate::impl_input_callback).
+ *
+ * ```ignore
+ * struct Silence;
+ *
+ * oboe::impl_output_callback!(Silence, (i16, Stereo), |_this, _stream, data| {
+ *     data.fill((0, 0));
+ *     DataCallbackResult::Continue
+ * });
+ * ```
+ */
+#[macro_export]
+macro_rules! impl_output_callback {
+    ($type:ty, $frame:ty, |$this:ident, $stream:ident, $data:ident| $body:expr) => {
+        impl $crate::AudioOutputCallback for $type {
+            type FrameType = $frame;
+
+            fn on_audio_ready(
+                &mut self,
+                $stream: &mut dyn $crate::AudioOutputStreamSafe,
+                $data: &mut [<$frame as $crate::IsFrameType>::Type],
+            ) -> $crate::DataCallbackResult {
+                let $this = self;
+                $body
+            }
+        }
+    };
+}
+
+/**
+ * The position of the last frame to present
+ *
+ * The marker is shared between [`PresentationEndCallback`] and the code which knows where the
+ * current track ends, so it can be set from the data callback as well as from other threads.
+ */
+#[derive(Debug, Clone)]
+pub struct PresentationEndMarker {
+    end_frame: Arc<AtomicI64>,
+}
+
+impl Default for PresentationEndMarker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PresentationEndMarker {
+    const NONE: i64 = -1;
+
+    /**
+     * Create unset marker
+     */
+    pub fn new() -> Self {
+        Self {
+            end_frame: Arc::new(AtomicI64::new(Self::NONE)),
+        }
+    }
+
+    /**
+     * Set the position of the last frame in terms of the frames written counter
+     *
+     * Usually it is `stream.get_frames_written()` plus the offset of the end of the track
+     * in the current buffer.
+     */
+    pub fn set(&self, end_frame: i64) {
+        self.end_frame.store(end_frame, Ordering::Release);
+    }
+
+    /**
+     * Unset the marker
+     */
+    pub fn clear(&self) {
+        self.end_frame.store(Self::NONE, Ordering::Release);
+    }
+
+    /**
+     * Get the position of the last frame when the marker is set
+     */
+    pub fn get(&self) -> Option<i64> {
+        let end_frame = self.end_frame.load(Ordering::Acquire);
+        if end_frame < 0 {
+            None
+        } else {
+            Some(end_frame)
+        }
+    }
+
+    fn take_reached(&self, frames_presented: i64) -> bool {
+        self.get()
+            .filter(|end_frame| frames_presented >= *end_frame)
+            .map(|end_frame| {
+                self.end_frame
+                    .compare_exchange(end_frame, Self::NONE, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+            })
+            .unwrap_or(false)
+    }
+}
+
+/**
+ * The output callback adapter which notifies when the marked frame was presented
+ *
+ * It is useful for gapless playback to know when the current track actually finished playing.
+ *
+ * Oboe does not expose AAudio presentation end callback, so this adapter approximates it by
+ * comparing [`AudioOutputStreamSafe::get_frames_presented`] with the position from
+ * [`PresentationEndMarker`] after each data callback. So the accuracy is limited:
+ *
+ * - the handler is called from the data callback, up to one callback period too late
+ * - the frames read counter is estimated by the system, especially on OpenSL ES, so
+ *   the handler may be called slightly before or after the frame actually leaves the device
+ * - the output latency of the device (e.g. bluetooth headsets) is not taken into account
+ *
+ * The handler is called from the audio thread, so it should not block.
+ */
+pub struct PresentationEndCallback<T, F> {
+    callback: T,
+    handler: F,
+    marker: PresentationEndMarker,
+}
+
+impl<T, F> PresentationEndCallback<T, F>
+where
+    T: AudioOutputCallback,
+    F: FnMut(&mut dyn AudioOutputStreamSafe),
+{
+    /**
+     * Wrap the output callback with presentation end handler
+     */
+    pub fn new(callback: T, handler: F) -> Self {
+        Self {
+            callback,
+            handler,
+            marker: PresentationEndMarker::new(),
+        }
+    }
+
+    /**
+     * Get the marker to set the position of the last frame
+     */
+    pub fn marker(&self) -> PresentationEndMarker {
+        self.marker.clone()
+    }
+}
+
+impl<T, F> AudioOutputCallback for PresentationEndCallback<T, F>
+where
+    T: AudioOutputCallback,
+    F: FnMut(&mut dyn AudioOutputStreamSafe),
+{
+    type FrameType = T::FrameType;
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        let result = self.callback.on_audio_ready(audio_stream, audio_data);
+
+        if self
+            .marker
+            .take_reached(audio_stream.get_frames_presented())
+        {
+            (self.handler)(audio_stream);
+        }
+
+        result
+    }
+}
+
 #[repr(transparent)]
 struct AudioStreamCallbackWrapperHandle(*mut ffi::oboe_AudioStreamCallbackWrapper);
 
@@ -218,14 +686,18 @@ impl AudioStreamCallbackWrapperHandle {
         after_close: ffi::oboe_ErrorCloseHandler,
     ) -> Self {
         Self(unsafe {
-            ffi::oboe_AudioStreamCallbackWrapper_new(audio_ready, before_close, after_close)
+            ffi_call!(oboe_AudioStreamCallbackWrapper_new(
+                audio_ready,
+                before_close,
+                after_close
+            ))
         })
     }
 }
 
 impl Drop for AudioStreamCallbackWrapperHandle {
     fn drop(&mut self) {
-        unsafe { ffi::oboe_AudioStreamCallbackWrapper_delete(self.0) }
+        unsafe { ffi_call!(oboe_AudioStreamCallbackWrapper_delete(self.0)) }
     }
 }
 
@@ -243,16 +715,182 @@ impl DerefMut for AudioStreamCallbackWrapperHandle {
     }
 }
 
+/**
+ * The panic which occurred in the user callback
+ */
+#[derive(Default)]
+pub(crate) struct CallbackPanic {
+    panicked: AtomicBool,
+    message: Mutex<Option<String>>,
+}
+
+impl CallbackPanic {
+    pub(crate) fn is_panicked(&self) -> bool {
+        self.panicked.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn take_message(&self) -> Option<String> {
+        self.message
+            .lock()
+            .map(|mut message| message.take())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, payload: Box<dyn Any + Send>) {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Unknown panic".into()
+        };
+
+        if let Ok(mut stored) = self.message.lock() {
+            *stored = Some(message);
+        }
+        self.panicked.store(true, Ordering::Release);
+    }
+
+    /// Run the user code and catch the panic to prevent unwinding into C++
+    fn guard<R>(&self, default: R, f: impl FnOnce() -> R) -> R {
+        if self.is_panicked() {
+            return default;
+        }
+        match catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => result,
+            Err(payload) => {
+                self.record(payload);
+                default
+            }
+        }
+    }
+}
+
+/**
+ * The last error reported to the error callback
+ */
+#[derive(Default)]
+pub(crate) struct CallbackError {
+    code: AtomicI32,
+}
+
+impl CallbackError {
+    pub(crate) fn get(&self) -> Option<Error> {
+        match self.code.load(Ordering::Acquire) {
+            ffi::oboe_Result_OK => None,
+            code => Some(Error::from_code(code)),
+        }
+    }
+
+    fn record(&self, code: ffi::oboe_Result) {
+        self.code.store(code, Ordering::Release);
+    }
+
+    fn clear(&self) {
+        self.code.store(ffi::oboe_Result_OK, Ordering::Release);
+    }
+}
+
+struct CallbackContext<T> {
+    callback: T,
+    panic: Arc<CallbackPanic>,
+    error: Arc<CallbackError>,
+    xrun_count: i32,
+    discard_frames: usize,
+    discard_left: usize,
+    hint_target: Arc<PerformanceHintTarget>,
+    hint_reporter: PerformanceHintReporter,
+}
+
+impl<T> CallbackContext<T> {
+    /// The number of xruns since the previous data callback
+    fn xrun_delta<S: AudioStreamSafe>(&mut self, audio_stream: &S) -> i32 {
+        match audio_stream.get_xrun_count() {
+            Ok(count) => {
+                let delta = count - self.xrun_count;
+                self.xrun_count = count;
+                delta
+            }
+            Err(_) => 0,
+        }
+    }
+}
+
 pub(crate) struct AudioCallbackWrapper<D, T> {
     raw: AudioStreamCallbackWrapperHandle,
-    callback: Box<T>,
+    context: Box<CallbackContext<T>>,
+    panic: Arc<CallbackPanic>,
+    error: Arc<CallbackError>,
+    hint_target: Arc<PerformanceHintTarget>,
+    reserve: Option<fn(&mut T, usize)>,
     _phantom: PhantomData<D>,
 }
 
 impl<D, T> AudioCallbackWrapper<D, T> {
+    /// The only place where the callback state is allocated, see `AudioStreamBuilderAsync`
+    fn new(raw: AudioStreamCallbackWrapperHandle, callback: T) -> Self {
+        let panic = Arc::new(CallbackPanic::default());
+        let error = Arc::new(CallbackError::default());
+        let hint_target = Arc::new(PerformanceHintTarget::default());
+        let mut wrapper = Self {
+            raw,
+            context: Box::new(CallbackContext {
+                callback,
+                panic: panic.clone(),
+                error: error.clone(),
+                xrun_count: 0,
+                discard_frames: 0,
+                discard_left: 0,
+                hint_target: hint_target.clone(),
+                hint_reporter: Default::default(),
+            }),
+            panic,
+            error,
+            hint_target,
+            reserve: None,
+            _phantom: PhantomData,
+        };
+        unsafe {
+            (*wrapper.raw).setContext(&mut (*wrapper.context) as *mut _ as *mut c_void);
+        }
+        wrapper
+    }
+
     pub(crate) fn raw_callback(&mut self) -> &mut ffi::oboe_AudioStreamCallbackWrapper {
         &mut *self.raw
     }
+
+    pub(crate) fn panic(&self) -> &CallbackPanic {
+        &self.panic
+    }
+
+    pub(crate) fn error(&self) -> &CallbackError {
+        &self.error
+    }
+
+    pub(crate) fn performance_hint_target(&self) -> &PerformanceHintTarget {
+        &self.hint_target
+    }
+
+    pub(crate) fn callback_mut(&mut self) -> &mut T {
+        &mut self.context.callback
+    }
+
+    /// Let the callback preallocate for up to `frames` frames, the callbacks must not run
+    pub(crate) fn reserve_frames(&mut self, frames: usize) {
+        if let Some(reserve) = self.reserve {
+            reserve(&mut self.context.callback, frames);
+        }
+    }
+
+    /// Forget the state of previous stream before reusing the callback with a new one
+    pub(crate) fn reset_stream_state(&mut self) {
+        self.context.xrun_count = 0;
+        self.context.discard_left = self.context.discard_frames;
+        // The hint session is bound to the callback thread of previous stream
+        self.context.hint_reporter = Default::default();
+        self.error.clear();
+    }
 }
 
 impl<T> AudioCallbackWrapper<Input, T>
@@ -260,21 +898,38 @@ where
     T: AudioInputCallback,
 {
     pub(crate) fn wrap(callback: T) -> Self {
-        let callback = Box::new(callback);
-        let mut wrapper = Self {
-            raw: AudioStreamCallbackWrapperHandle::new(
+        Self::new(
+            AudioStreamCallbackWrapperHandle::new(
                 Some(on_audio_ready_input_wrapper::<T>),
                 Some(on_error_before_close_input_wrapper::<T>),
                 Some(on_error_after_close_input_wrapper::<T>),
             ),
             callback,
-            _phantom: PhantomData,
-        };
-        unsafe {
-            (*wrapper.raw).setContext(&mut (*wrapper.callback) as *mut _ as *mut c_void);
-        }
+        )
+    }
+
+    /// Wrap the callback which handles the frames in the format negotiated by stream
+    pub(crate) fn wrap_native(callback: T) -> Self
+    where
+        T: NativeInputCallback,
+    {
+        let mut wrapper = Self::new(
+            AudioStreamCallbackWrapperHandle::new(
+                Some(on_native_audio_ready_input_wrapper::<T>),
+                Some(on_error_before_close_input_wrapper::<T>),
+                Some(on_error_after_close_input_wrapper::<T>),
+            ),
+            callback,
+        );
+        wrapper.reserve = Some(T::reserve_frames);
         wrapper
     }
+
+    /// Skip the captured frames before passing them to the callback
+    pub(crate) fn set_discard_initial_frames(&mut self, frames: usize) {
+        self.context.discard_frames = frames;
+        self.context.discard_left = frames;
+    }
 }
 
 impl<T> AudioCallbackWrapper<Output, T>
@@ -282,19 +937,30 @@ where
     T: AudioOutputCallback,
 {
     pub(crate) fn wrap(callback: T) -> Self {
-        let callback = Box::new(callback);
-        let mut wrapper = Self {
-            raw: AudioStreamCallbackWrapperHandle::new(
+        Self::new(
+            AudioStreamCallbackWrapperHandle::new(
                 Some(on_audio_ready_output_wrapper::<T>),
                 Some(on_error_before_close_output_wrapper::<T>),
                 Some(on_error_after_close_output_wrapper::<T>),
             ),
             callback,
-            _phantom: PhantomData,
-        };
-        unsafe {
-            (*wrapper.raw).setContext(&mut (*wrapper.callback) as *mut _ as *mut c_void);
-        }
+        )
+    }
+
+    /// Wrap the callback which handles the frames in the format negotiated by stream
+    pub(crate) fn wrap_native(callback: T) -> Self
+    where
+        T: NativeOutputCallback,
+    {
+        let mut wrapper = Self::new(
+            AudioStreamCallbackWrapperHandle::new(
+                Some(on_native_audio_ready_output_wrapper::<T>),
+                Some(on_error_before_close_output_wrapper::<T>),
+                Some(on_error_after_close_output_wrapper::<T>),
+            ),
+            callback,
+        );
+        wrapper.reserve = Some(T::reserve_frames);
         wrapper
     }
 }
@@ -305,9 +971,13 @@ unsafe extern "C" fn on_error_before_close_input_wrapper<T: AudioInputCallback>(
     error: ffi::oboe_Result,
 ) {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
 
-    callback.on_error_before_close(&mut audio_stream, FromPrimitive::from_i32(error).unwrap());
+    context.error.record(error);
+    context.panic.guard((), || {
+        callback.on_error_before_close(&mut audio_stream, Error::from_code(error))
+    });
 }
 
 unsafe extern "C" fn on_error_after_close_input_wrapper<T: AudioInputCallback>(
@@ -316,9 +986,12 @@ unsafe extern "C" fn on_error_after_close_input_wrapper<T: AudioInputCallback>(
     error: ffi::oboe_Result,
 ) {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
 
-    callback.on_error_after_close(&mut audio_stream, FromPrimitive::from_i32(error).unwrap());
+    context.panic.guard((), || {
+        callback.on_error_after_close(&mut audio_stream, Error::from_code(error))
+    });
 }
 
 unsafe extern "C" fn on_audio_ready_input_wrapper<T: AudioInputCallback>(
@@ -334,9 +1007,62 @@ unsafe extern "C" fn on_audio_ready_input_wrapper<T: AudioInputCallback>(
         num_frames as usize,
     );
 
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let xrun_delta = context.xrun_delta(&audio_stream);
+
+    let discard = context.discard_left.min(audio_data.len());
+    context.discard_left -= discard;
+    if discard > 0 && discard == audio_data.len() {
+        return DataCallbackResult::Continue as i32;
+    }
+    let audio_data = &audio_data[discard..];
+
+    let hint_start = context.hint_reporter.begin(&context.hint_target);
+    let callback = &mut context.callback;
+
+    let result = context.panic.guard(DataCallbackResult::Stop, || {
+        if xrun_delta > 0 {
+            callback.on_xrun(&mut audio_stream, xrun_delta);
+        }
+        callback.on_audio_ready(&mut audio_stream, audio_data)
+    });
+
+    context.hint_reporter.end(hint_start);
+    result as i32
+}
+
+unsafe extern "C" fn on_native_audio_ready_input_wrapper<T: NativeInputCallback>(
+    context: *mut c_void,
+    audio_stream: *mut ffi::oboe_AudioStream,
+    audio_data: *mut c_void,
+    num_frames: i32,
+) -> ffi::oboe_DataCallbackResult {
+    let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
+    let num_frames = num_frames as usize;
+
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let xrun_delta = context.xrun_delta(&audio_stream);
 
-    callback.on_audio_ready(&mut audio_stream, audio_data) as i32
+    let discard = context.discard_left.min(num_frames);
+    context.discard_left -= discard;
+    if discard > 0 && discard == num_frames {
+        return DataCallbackResult::Continue as i32;
+    }
+    let bytes_per_frame = audio_stream.get_bytes_per_frame() as usize;
+    let audio_data = (audio_data as *const u8).add(discard * bytes_per_frame) as *const c_void;
+
+    let hint_start = context.hint_reporter.begin(&context.hint_target);
+    let callback = &mut context.callback;
+
+    let result = context.panic.guard(DataCallbackResult::Stop, || {
+        if xrun_delta > 0 {
+            callback.on_xrun(&mut audio_stream, xrun_delta);
+        }
+        callback.on_native_audio_ready(&mut audio_stream, audio_data, num_frames - discard)
+    });
+
+    context.hint_reporter.end(hint_start);
+    result as i32
 }
 
 unsafe extern "C" fn on_error_before_close_output_wrapper<T: AudioOutputCallback>(
@@ -345,10 +1071,13 @@ unsafe extern "C" fn on_error_before_close_output_wrapper<T: AudioOutputCallback
     error: ffi::oboe_Result,
 ) {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
 
-    let callback = &mut *(context as *mut T);
-
-    callback.on_error_before_close(&mut audio_stream, FromPrimitive::from_i32(error).unwrap());
+    context.error.record(error);
+    context.panic.guard((), || {
+        callback.on_error_before_close(&mut audio_stream, Error::from_code(error))
+    });
 }
 
 unsafe extern "C" fn on_error_after_close_output_wrapper<T: AudioOutputCallback>(
@@ -357,9 +1086,12 @@ unsafe extern "C" fn on_error_after_close_output_wrapper<T: AudioOutputCallback>
     error: ffi::oboe_Result,
 ) {
     let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let callback = &mut context.callback;
 
-    callback.on_error_after_close(&mut audio_stream, FromPrimitive::from_i32(error).unwrap());
+    context.panic.guard((), || {
+        callback.on_error_after_close(&mut audio_stream, Error::from_code(error))
+    });
 }
 
 unsafe extern "C" fn on_audio_ready_output_wrapper<T: AudioOutputCallback>(
@@ -375,7 +1107,42 @@ unsafe extern "C" fn on_audio_ready_output_wrapper<T: AudioOutputCallback>(
         num_frames as usize,
     );
 
-    let callback = &mut *(context as *mut T);
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let xrun_delta = context.xrun_delta(&audio_stream);
+    let hint_start = context.hint_reporter.begin(&context.hint_target);
+    let callback = &mut context.callback;
+
+    let result = context.panic.guard(DataCallbackResult::Stop, || {
+        if xrun_delta > 0 {
+            callback.on_xrun(&mut audio_stream, xrun_delta);
+        }
+        callback.on_audio_ready(&mut audio_stream, audio_data)
+    });
+
+    context.hint_reporter.end(hint_start);
+    result as i32
+}
+
+unsafe extern "C" fn on_native_audio_ready_output_wrapper<T: NativeOutputCallback>(
+    context: *mut c_void,
+    audio_stream: *mut ffi::oboe_AudioStream,
+    audio_data: *mut c_void,
+    num_frames: i32,
+) -> ffi::oboe_DataCallbackResult {
+    let mut audio_stream = AudioStreamRef::wrap_raw(&mut *audio_stream);
+
+    let context = &mut *(context as *mut CallbackContext<T>);
+    let xrun_delta = context.xrun_delta(&audio_stream);
+    let hint_start = context.hint_reporter.begin(&context.hint_target);
+    let callback = &mut context.callback;
+
+    let result = context.panic.guard(DataCallbackResult::Stop, || {
+        if xrun_delta > 0 {
+            callback.on_xrun(&mut audio_stream, xrun_delta);
+        }
+        callback.on_native_audio_ready(&mut audio_stream, audio_data, num_frames as usize)
+    });
 
-    callback.on_audio_ready(&mut audio_stream, audio_data) as i32
+    context.hint_reporter.end(hint_start);
+    result as i32
 }
@@ -0,0 +1,290 @@
+use std::marker::PhantomData;
+
+use super::{
+    AudioApi, AudioFormat, AudioInputCallback, AudioInputStreamSafe, AudioOutputCallback,
+    AudioOutputStreamSafe, AudioStreamBase, AudioStreamSafe, ChannelCount, ClockId, ContentType,
+    DataCallbackResult, Direction, Error, FrameTimestamp, Input, InputPreset, IsChannelCount,
+    IsDirection, IsFormat, IsFrameType, Output, PerformanceMode, Result,
+    SampleRateConversionQuality, SessionId, SharingMode, StreamState, Usage,
+};
+
+/**
+ * The deterministic driver of callbacks for tests
+ *
+ * Available with `test-driver` feature.
+ *
+ * It calls the callback directly without opening any stream, so the callbacks can be tested
+ * on the host sample by sample without timing issues. The stream passed to the callback is
+ * emulated: it reports the parameters of the driver and counts the frames processed so far.
+ *
+ * ```ignore
+ * let mut driver = CallbackDriver::output(MySynth::default(), 48000);
+ * let frames = driver.render(256);
+ * assert_eq!(frames, golden_reference[..256]);
+ * ```
+ */
+pub struct CallbackDriver<D, C> {
+    callback: C,
+    stream: DriverStream,
+    result: Option<DataCallbackResult>,
+    _phantom: PhantomData<D>,
+}
+
+impl<D: IsDirection, C> CallbackDriver<D, C> {
+    fn new<F: IsFrameType>(callback: C, sample_rate: i32) -> Self {
+        Self {
+            callback,
+            stream: DriverStream {
+                direction: D::DIRECTION,
+                channel_count: F::ChannelCount::CHANNEL_COUNT,
+                format: F::Format::FORMAT,
+                sample_rate,
+                frames: 0,
+                state: StreamState::Started,
+            },
+            result: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /**
+     * Get the result returned by the last data callback
+     */
+    pub fn last_result(&self) -> Option<DataCallbackResult> {
+        self.result
+    }
+
+    /**
+     * Get the number of frames processed so far
+     */
+    pub fn frames_processed(&self) -> i64 {
+        self.stream.frames
+    }
+
+    /**
+     * Get the callback
+     */
+    pub fn callback(&mut self) -> &mut C {
+        &mut self.callback
+    }
+
+    fn finish(&mut self, result: DataCallbackResult) {
+        if result == DataCallbackResult::Stop {
+            self.stream.state = StreamState::Stopped;
+        }
+        self.result = Some(result);
+    }
+}
+
+impl<C> CallbackDriver<Output, C>
+where
+    C: AudioOutputCallback,
+    <C::FrameType as IsFrameType>::Type: Clone + Default,
+{
+    /**
+     * Create the driver of output callback with specified sample rate
+     */
+    pub fn output(callback: C, sample_rate: i32) -> Self {
+        Self::new::<C::FrameType>(callback, sample_rate)
+    }
+
+    /**
+     * Call the data callback exactly once with the buffer of `frames` frames
+     *
+     * Returns the frames rendered by the callback.
+     */
+    pub fn render(&mut self, frames: usize) -> Vec<<C::FrameType as IsFrameType>::Type> {
+        let mut buffer = vec![Default::default(); frames];
+        let result = self.callback.on_audio_ready(&mut self.stream, &mut buffer);
+        self.stream.frames += frames as i64;
+        self.finish(result);
+        buffer
+    }
+
+    /**
+     * Call the error callbacks as if the stream was closed because of `error`
+     */
+    pub fn fail(&mut self, error: Error) {
+        self.stream.state = StreamState::Disconnected;
+        self.callback.on_error_before_close(&mut self.stream, error);
+        self.stream.state = StreamState::Closed;
+        self.callback.on_error_after_close(&mut self.stream, error);
+    }
+}
+
+impl<C> CallbackDriver<Input, C>
+where
+    C: AudioInputCallback,
+{
+    /**
+     * Create the driver of input callback with specified sample rate
+     */
+    pub fn input(callback: C, sample_rate: i32) -> Self {
+        Self::new::<C::FrameType>(callback, sample_rate)
+    }
+
+    /**
+     * Call the data callback exactly once with the captured frames
+     */
+    pub fn feed(&mut self, frames: &[<C::FrameType as IsFrameType>::Type]) -> DataCallbackResult {
+        let result = self.callback.on_audio_ready(&mut self.stream, frames);
+        self.stream.frames += frames.len() as i64;
+        self.finish(result);
+        result
+    }
+
+    /**
+     * Call the error callbacks as if the stream was closed because of `error`
+     */
+    pub fn fail(&mut self, error: Error) {
+        self.stream.state = StreamState::Disconnected;
+        self.callback.on_error_before_close(&mut self.stream, error);
+        self.stream.state = StreamState::Closed;
+        self.callback.on_error_after_close(&mut self.stream, error);
+    }
+}
+
+/// The emulated stream which is passed to the callbacks by driver
+struct DriverStream {
+    direction: Direction,
+    channel_count: ChannelCount,
+    format: AudioFormat,
+    sample_rate: i32,
+    frames: i64,
+    state: StreamState,
+}
+
+impl AudioStreamBase for DriverStream {
+    fn get_channel_count(&self) -> ChannelCount {
+        self.channel_count
+    }
+
+    fn get_direction(&self) -> Direction {
+        self.direction
+    }
+
+    fn get_sample_rate(&self) -> i32 {
+        self.sample_rate
+    }
+
+    fn get_frames_per_callback(&self) -> i32 {
+        0
+    }
+
+    fn get_format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn get_buffer_size_in_frames(&self) -> i32 {
+        0
+    }
+
+    fn get_buffer_capacity_in_frames(&self) -> i32 {
+        0
+    }
+
+    fn get_sharing_mode(&self) -> SharingMode {
+        SharingMode::Shared
+    }
+
+    fn get_performance_mode(&self) -> PerformanceMode {
+        PerformanceMode::None
+    }
+
+    fn get_device_id(&self) -> i32 {
+        0
+    }
+
+    fn get_usage(&self) -> Usage {
+        Usage::Media
+    }
+
+    fn get_content_type(&self) -> ContentType {
+        ContentType::Music
+    }
+
+    fn get_input_preset(&self) -> InputPreset {
+        InputPreset::VoiceRecognition
+    }
+
+    fn get_session_id(&self) -> SessionId {
+        SessionId::None
+    }
+
+    fn get_allocated_session_id(&self) -> Option<i32> {
+        None
+    }
+
+    fn is_channel_conversion_allowed(&self) -> bool {
+        false
+    }
+
+    fn is_format_conversion_allowed(&self) -> bool {
+        false
+    }
+
+    fn get_sample_rate_conversion_quality(&self) -> SampleRateConversionQuality {
+        SampleRateConversionQuality::None
+    }
+}
+
+impl AudioStreamSafe for DriverStream {
+    fn get_state(&self) -> StreamState {
+        self.state
+    }
+
+    fn set_buffer_size_in_frames(&mut self, _requested_frames: i32) -> Result<i32> {
+        Err(Error::Unimplemented)
+    }
+
+    fn get_xrun_count(&self) -> Result<i32> {
+        Ok(0)
+    }
+
+    fn is_xrun_count_supported(&self) -> bool {
+        true
+    }
+
+    fn get_frames_per_burst(&mut self) -> i32 {
+        0
+    }
+
+    fn get_bytes_per_sample(&mut self) -> i32 {
+        self.format.bytes_per_sample() as i32
+    }
+
+    fn calculate_latency_millis(&mut self) -> Result<f64> {
+        Ok(0.0)
+    }
+
+    fn get_timestamp(&mut self, _clock_id: ClockId) -> Result<FrameTimestamp> {
+        Ok(FrameTimestamp {
+            position: self.frames,
+            timestamp: self.frames_to_duration(self.frames).as_nanos() as i64,
+        })
+    }
+
+    fn get_audio_api(&self) -> AudioApi {
+        AudioApi::Unspecified
+    }
+
+    fn get_available_frames(&mut self) -> Result<i32> {
+        Ok(0)
+    }
+}
+
+impl AudioInputStreamSafe for DriverStream {
+    fn get_frames_read(&mut self) -> i64 {
+        self.frames
+    }
+}
+
+impl AudioOutputStreamSafe for DriverStream {
+    fn get_frames_written(&mut self) -> i64 {
+        self.frames
+    }
+
+    fn get_frames_presented(&mut self) -> i64 {
+        self.frames
+    }
+}
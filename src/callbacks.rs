@@ -0,0 +1,30 @@
+mod buffer_player;
+mod channel_map;
+#[cfg(feature = "symphonia")]
+mod decoder_source;
+mod fade;
+mod iterator_source;
+mod meter;
+mod mono_sum;
+mod noise_source;
+mod presentation_delay;
+#[cfg(feature = "rodio")]
+mod rodio_source;
+mod sample_ring;
+mod software_conversion;
+#[cfg(feature = "wav")]
+mod wav_writer;
+
+pub use self::buffer_player::*;
+pub use self::channel_map::*;
+#[cfg(feature = "symphonia")]
+pub use self::decoder_source::*;
+pub use self::fade::*;
+pub use self::iterator_source::*;
+pub use self::meter::*;
+pub use self::mono_sum::*;
+pub use self::noise_source::*;
+pub use self::presentation_delay::*;
+pub use self::software_conversion::*;
+#[cfg(feature = "wav")]
+pub use self::wav_writer::*;
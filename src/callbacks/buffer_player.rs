@@ -0,0 +1,179 @@
+use std::{
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    AudioOutputCallback, AudioOutputStreamSafe, AudioStreamBase, DataCallbackResult, Error,
+    IsFrameType, Result,
+};
+
+/**
+ * The output callback which plays back the buffer of frames
+ *
+ * The buffer can be played once, in that case the callback returns [`DataCallbackResult::Stop`]
+ * after the last frame, or in a loop which wraps seamlessly across the callback boundaries.
+ *
+ * The current position is stored in atomic, so it can be read by other threads
+ * using [`BufferPlayer::position`].
+ *
+ * No resampling is performed by the player. When the sample rate of the opened stream differs
+ * from the sample rate of frames, e.g. because the device rate was chosen for unspecified rate,
+ * the callback outputs silence and returns [`DataCallbackResult::Stop`].
+ */
+pub struct BufferPlayer<T: IsFrameType> {
+    frames: Vec<T::Type>,
+    sample_rate: i32,
+    looping: bool,
+    position: Arc<AtomicUsize>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: IsFrameType> BufferPlayer<T>
+where
+    T::Type: Copy + Default,
+{
+    /**
+     * Create player of frames with specified sample rate for the stream builder
+     *
+     * Returns [`Error::InvalidRate`] when the sample rate of builder is specified and does not
+     * match the sample rate of frames. Set it using
+     * [`AudioStreamBuilder::set_sample_rate`](crate::AudioStreamBuilder::set_sample_rate)
+     * before creating the player, so the stream is opened with the rate of frames.
+     */
+    pub fn new<B: AudioStreamBase>(
+        builder: &B,
+        frames: Vec<T::Type>,
+        sample_rate: i32,
+        looping: bool,
+    ) -> Result<Self> {
+        let requested = builder.get_sample_rate();
+        if requested > 0 && requested != sample_rate {
+            return Err(Error::InvalidRate);
+        }
+
+        Ok(Self {
+            frames,
+            sample_rate,
+            looping,
+            position: Default::default(),
+            _phantom: PhantomData,
+        })
+    }
+
+    /**
+     * Get the shared position of the next frame to play
+     */
+    pub fn position(&self) -> Arc<AtomicUsize> {
+        self.position.clone()
+    }
+
+    /**
+     * Get the number of frames in buffer
+     */
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /**
+     * Check that the buffer has no frames
+     */
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /**
+     * Check that the buffer is played in a loop
+     */
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+}
+
+impl<T: IsFrameType> AudioOutputCallback for BufferPlayer<T>
+where
+    T::Type: Copy + Default,
+{
+    type FrameType = T;
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [T::Type],
+    ) -> DataCallbackResult {
+        // The rate of opened stream may differ from the requested one
+        if audio_stream.get_sample_rate() != self.sample_rate {
+            for frame in audio_data.iter_mut() {
+                *frame = Default::default();
+            }
+            return DataCallbackResult::Stop;
+        }
+
+        let frames = &self.frames[..];
+        let mut position = self.position.load(Ordering::Relaxed);
+        let mut written = 0;
+
+        while written < audio_data.len() {
+            if position >= frames.len() {
+                if self.looping && !frames.is_empty() {
+                    position = 0;
+                } else {
+                    break;
+                }
+            }
+
+            let count = (audio_data.len() - written).min(frames.len() - position);
+            audio_data[written..written + count]
+                .copy_from_slice(&frames[position..position + count]);
+            written += count;
+            position += count;
+        }
+
+        for frame in &mut audio_data[written..] {
+            *frame = Default::default();
+        }
+
+        self.position.store(position, Ordering::Relaxed);
+
+        if !self.looping && position >= frames.len() {
+            DataCallbackResult::Stop
+        } else {
+            DataCallbackResult::Continue
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-driver"))]
+mod tests {
+    use super::*;
+    use crate::{test_stream::TestStream, CallbackDriver, Mono};
+
+    fn player(builder_rate: i32) -> Result<BufferPlayer<(f32, Mono)>> {
+        let mut builder = TestStream::new();
+        builder.sample_rate = builder_rate;
+        BufferPlayer::new(&builder, vec![0.5; 4], 44100, false)
+    }
+
+    #[test]
+    fn rejects_requested_rate_mismatch() {
+        assert_eq!(player(48000).err(), Some(Error::InvalidRate));
+    }
+
+    #[test]
+    fn plays_at_negotiated_rate() {
+        let mut driver = CallbackDriver::output(player(0).unwrap(), 44100);
+        assert_eq!(driver.render(6), [0.5, 0.5, 0.5, 0.5, 0.0, 0.0]);
+        assert_eq!(driver.last_result(), Some(DataCallbackResult::Stop));
+    }
+
+    #[test]
+    fn stops_at_negotiated_rate_mismatch() {
+        let mut driver = CallbackDriver::output(player(0).unwrap(), 48000);
+        assert_eq!(driver.render(2), [0.0, 0.0]);
+        assert_eq!(driver.last_result(), Some(DataCallbackResult::Stop));
+        assert_eq!(driver.callback().position().load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    AudioOutputCallback, AudioOutputStreamSafe, DataCallbackResult, Error, IsFrame, IsFrameType,
+    IsSample,
+};
+
+enum Routing {
+    Permutation(Vec<usize>),
+    Matrix(Vec<f32>),
+}
+
+/**
+ * The output callback adapter which routes the rendered channels to the device channels
+ *
+ * It is configured at construction either by the permutation, e.g. to swap left and right
+ * on the device with the unusual channel order, or by the mix matrix, e.g. to downmix.
+ * The routing is applied to each frame after the wrapped callback renders it, so the callback
+ * works in its own logical channel order.
+ *
+ * The stream has the same number of channels as the wrapped callback.
+ */
+pub struct ChannelMapCallback<T> {
+    callback: T,
+    routing: Routing,
+}
+
+impl<T> ChannelMapCallback<T>
+where
+    T: AudioOutputCallback,
+    <T::FrameType as IsFrameType>::Type: IsFrame,
+{
+    const CHANNELS: usize = <<T::FrameType as IsFrameType>::Type as IsFrame>::CHANNELS;
+
+    /**
+     * Wrap the output callback to reorder its channels
+     *
+     * The device channel `i` gets the rendered channel `map[i]`, so `[1, 0]` swaps
+     * left and right. The same channel may be routed to several device channels.
+     *
+     * Panics when the length of map is not the number of channels, or the index is out of range.
+     */
+    pub fn permutation(callback: T, map: &[usize]) -> Self {
+        assert_eq!(map.len(), Self::CHANNELS, "Channel map size mismatch");
+        assert!(
+            map.iter().all(|&channel| channel < Self::CHANNELS),
+            "Channel index out of range"
+        );
+
+        Self {
+            callback,
+            routing: Routing::Permutation(map.to_vec()),
+        }
+    }
+
+    /**
+     * Wrap the output callback to mix its channels using the matrix
+     *
+     * The matrix has a row of gains for each device channel, and a column for each rendered
+     * channel, in row-major order. E.g. `[0.5, 0.5, 0.5, 0.5]` downmixes stereo to mono
+     * on both channels. The mixed samples are clipped to the range of sample type.
+     *
+     * Panics when the matrix is not square with side of the number of channels.
+     */
+    pub fn matrix(callback: T, matrix: &[f32]) -> Self {
+        assert_eq!(
+            matrix.len(),
+            Self::CHANNELS * Self::CHANNELS,
+            "Channel matrix size mismatch"
+        );
+
+        Self {
+            callback,
+            routing: Routing::Matrix(matrix.to_vec()),
+        }
+    }
+}
+
+impl<T> AudioOutputCallback for ChannelMapCallback<T>
+where
+    T: AudioOutputCallback,
+    <T::FrameType as IsFrameType>::Type: IsFrame,
+{
+    type FrameType = T::FrameType;
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        let result = self.callback.on_audio_ready(audio_stream, audio_data);
+        let channels = Self::CHANNELS;
+
+        match &self.routing {
+            Routing::Permutation(map) => {
+                for frame in audio_data.iter_mut() {
+                    let rendered = *frame;
+                    for (output, &input) in map.iter().enumerate() {
+                        frame.set_channel(output, rendered.channel(input));
+                    }
+                }
+            }
+            Routing::Matrix(matrix) => {
+                for frame in audio_data.iter_mut() {
+                    let rendered = *frame;
+                    for (output, gains) in matrix.chunks_exact(channels).enumerate() {
+                        let mixed = gains
+                            .iter()
+                            .enumerate()
+                            .map(|(input, gain)| gain * rendered.channel(input).to_f32())
+                            .sum::<f32>();
+                        frame.set_channel(output, IsSample::from_f32(mixed.clamp(-1.0, 1.0)));
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    marker::PhantomData,
+    slice::from_raw_parts_mut,
+    sync::{atomic::Ordering, Arc},
+    thread::{self, JoinHandle},
+};
+
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions},
+    conv::ConvertibleSample,
+    errors::Error as DecoderError,
+    formats::FormatReader,
+};
+
+use crate::{
+    AudioOutputCallback, AudioOutputStreamSafe, AudioStreamBase, DataCallbackResult, IsFormat,
+    IsFrameType, Mono, Stereo,
+};
+
+use super::sample_ring::SampleFeed;
+
+/**
+ * The output callback which plays audio decoded by `symphonia`
+ *
+ * Packets are decoded on a worker thread into an internal ring buffer, so the data
+ * callback never blocks. Decoded samples are converted to the sample format of the stream.
+ *
+ * The channels are mapped as follows:
+ *
+ * - mono stream takes the first channel of source
+ * - stereo stream takes the first two channels of source, mono source is duplicated
+ *
+ * The source is not resampled, so its sample rate must match the requested one. When the stream
+ * is opened with another sample rate, the callback outputs silence and returns
+ * [`DataCallbackResult::Stop`].
+ *
+ * When the source ends, the rest of buffered audio is played, then the callback returns
+ * [`DataCallbackResult::Stop`]. Buffer underruns are filled by silence.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "symphonia")))]
+pub struct DecoderSource<F: IsFrameType> {
+    shared: Arc<SampleFeed<F::Format>>,
+    worker: Option<JoinHandle<()>>,
+    sample_rate: i32,
+    _phantom: PhantomData<F>,
+}
+
+impl<F> DecoderSource<F>
+where
+    F: IsFrameType,
+    F::Format: ConvertibleSample + Copy + Default + Send + Sync + 'static,
+{
+    fn spawn<B: AudioStreamBase>(
+        builder: &B,
+        reader: Box<dyn FormatReader>,
+        channels: usize,
+        buffer_frames: usize,
+    ) -> Result<Self, DecoderError> {
+        let track = reader
+            .default_track()
+            .ok_or(DecoderError::Unsupported("No default track"))?;
+        let track_id = track.id;
+
+        let sample_rate = builder.get_sample_rate();
+        if track.codec_params.sample_rate != Some(sample_rate as u32) {
+            return Err(DecoderError::Unsupported("Sample rate mismatch"));
+        }
+
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        let shared = Arc::new(SampleFeed::new(buffer_frames.max(1) * channels));
+
+        let worker = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                decode_worker(&shared, reader, decoder, track_id, channels);
+                shared.finished.store(true, Ordering::Release);
+            })
+        };
+
+        Ok(Self {
+            shared,
+            worker: Some(worker),
+            sample_rate,
+            _phantom: PhantomData,
+        })
+    }
+
+    /**
+     * Check that the source ended and all buffered audio was played
+     */
+    pub fn is_finished(&self) -> bool {
+        self.shared.is_finished()
+    }
+
+    fn render(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        samples: &mut [F::Format],
+    ) -> DataCallbackResult {
+        // The rate of opened stream may differ from the requested one
+        if audio_stream.get_sample_rate() != self.sample_rate {
+            for sample in samples.iter_mut() {
+                *sample = Default::default();
+            }
+            return DataCallbackResult::Stop;
+        }
+
+        if self.shared.render(samples) {
+            DataCallbackResult::Continue
+        } else {
+            DataCallbackResult::Stop
+        }
+    }
+}
+
+impl<T> DecoderSource<(T, Mono)>
+where
+    T: IsFormat + ConvertibleSample + Copy + Default + Send + Sync + 'static,
+{
+    /**
+     * Start decoding the default track of reader for mono stream
+     *
+     * The `buffer_frames` is the capacity of the internal buffer.
+     * Returns [`Unsupported`](DecoderError::Unsupported) error when the sample rate of track
+     * does not match the sample rate requested by builder.
+     */
+    pub fn new<B: AudioStreamBase>(
+        builder: &B,
+        reader: Box<dyn FormatReader>,
+        buffer_frames: usize,
+    ) -> Result<Self, DecoderError> {
+        Self::spawn(builder, reader, 1, buffer_frames)
+    }
+}
+
+impl<T> DecoderSource<(T, Stereo)>
+where
+    T: IsFormat + ConvertibleSample + Copy + Default + Send + Sync + 'static,
+{
+    /**
+     * Start decoding the default track of reader for stereo stream
+     *
+     * The `buffer_frames` is the capacity of the internal buffer.
+     * Returns [`Unsupported`](DecoderError::Unsupported) error when the sample rate of track
+     * does not match the sample rate requested by builder.
+     */
+    pub fn new<B: AudioStreamBase>(
+        builder: &B,
+        reader: Box<dyn FormatReader>,
+        buffer_frames: usize,
+    ) -> Result<Self, DecoderError> {
+        Self::spawn(builder, reader, 2, buffer_frames)
+    }
+}
+
+impl<F: IsFrameType> Drop for DecoderSource<F> {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Release);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<T> AudioOutputCallback for DecoderSource<(T, Mono)>
+where
+    T: IsFormat + ConvertibleSample + Copy + Default + Send + Sync + 'static,
+{
+    type FrameType = (T, Mono);
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [T],
+    ) -> DataCallbackResult {
+        self.render(audio_stream, audio_data)
+    }
+}
+
+impl<T> AudioOutputCallback for DecoderSource<(T, Stereo)>
+where
+    T: IsFormat + ConvertibleSample + Copy + Default + Send + Sync + 'static,
+{
+    type FrameType = (T, Stereo);
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [(T, T)],
+    ) -> DataCallbackResult {
+        let samples =
+            unsafe { from_raw_parts_mut(audio_data.as_mut_ptr() as *mut T, audio_data.len() * 2) };
+        self.render(audio_stream, samples)
+    }
+}
+
+fn decode_worker<T>(
+    shared: &SampleFeed<T>,
+    mut reader: Box<dyn FormatReader>,
+    mut decoder: Box<dyn Decoder>,
+    track_id: u32,
+    channels: usize,
+) where
+    T: ConvertibleSample + Copy + Default,
+{
+    let mut decoded_samples: Option<SampleBuffer<T>> = None;
+    let mut decoded_spec = None;
+    let mut samples = Vec::new();
+
+    while !shared.is_stopped() {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            // End of stream or unrecoverable error
+            Err(_) => break,
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Skip malformed packet
+            Err(DecoderError::DecodeError(_)) => continue,
+            Err(_) => break,
+        };
+
+        let spec = *decoded.spec();
+        let capacity = decoded.capacity();
+        let source_channels = spec.channels.count().max(1);
+
+        if decoded_spec != Some((spec, capacity)) {
+            decoded_samples = Some(SampleBuffer::new(capacity as u64, spec));
+            decoded_spec = Some((spec, capacity));
+        }
+
+        let buffer = decoded_samples.as_mut().unwrap();
+        buffer.copy_interleaved_ref(decoded);
+
+        samples.clear();
+        for frame in buffer.samples().chunks_exact(source_channels) {
+            for channel in 0..channels {
+                samples.push(frame[channel.min(source_channels - 1)]);
+            }
+        }
+
+        if !shared.push_all(&samples) {
+            return;
+        }
+    }
+}
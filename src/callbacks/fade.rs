@@ -0,0 +1,174 @@
+use std::{
+    f32::consts::FRAC_PI_2,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    AudioOutputCallback, AudioOutputStreamSafe, DataCallbackResult, Error, IsFrame, IsFrameType,
+    IsSample,
+};
+
+/**
+ * The shape of gain ramp
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeCurve {
+    /**
+     * The gain changes linearly
+     */
+    Linear,
+
+    /**
+     * The power is kept constant when fading between two sources
+     */
+    EqualPower,
+}
+
+impl FadeCurve {
+    fn gain(self, ramp: f32) -> f32 {
+        match self {
+            FadeCurve::Linear => ramp,
+            FadeCurve::EqualPower => (ramp * FRAC_PI_2).sin(),
+        }
+    }
+}
+
+/**
+ * The control of [`FadeCallback`] to request fading out
+ */
+#[derive(Debug, Clone, Default)]
+pub struct FadeControl {
+    stop: Arc<AtomicBool>,
+}
+
+impl FadeControl {
+    /**
+     * Request the fade-out, after which the callback stops the stream
+     */
+    pub fn request_stop(&self) {
+        self.stop.store(true, Ordering::Release);
+    }
+
+    /**
+     * Check that the fade-out was requested and not completed yet
+     */
+    pub fn is_stopping(&self) -> bool {
+        self.stop.load(Ordering::Acquire)
+    }
+}
+
+/**
+ * The output callback adapter which fades in on start and fades out on stop
+ *
+ * The abrupt start or stop of the stream causes clicks, so the adapter ramps
+ * the gain of the rendered frames up after the start, and down when the stop
+ * is requested using [`FadeControl::request_stop`]. The callback returns
+ * [`DataCallbackResult::Stop`] only after the fade-out is completed, so the
+ * stream stops in silence. After that the next start fades in again.
+ */
+pub struct FadeCallback<T> {
+    callback: T,
+    ramp: Duration,
+    curve: FadeCurve,
+    control: FadeControl,
+    fade_in: usize,
+    fade_out: usize,
+}
+
+impl<T> FadeCallback<T>
+where
+    T: AudioOutputCallback,
+    <T::FrameType as IsFrameType>::Type: IsFrame,
+{
+    /**
+     * Wrap the output callback with ramps of specified length in milliseconds
+     */
+    pub fn new(callback: T, ramp_millis: u32, curve: FadeCurve) -> Self {
+        Self {
+            callback,
+            ramp: Duration::from_millis(ramp_millis as u64),
+            curve,
+            control: Default::default(),
+            fade_in: 0,
+            fade_out: 0,
+        }
+    }
+
+    /**
+     * Get the control to request fading out
+     */
+    pub fn control(&self) -> FadeControl {
+        self.control.clone()
+    }
+}
+
+impl<T> AudioOutputCallback for FadeCallback<T>
+where
+    T: AudioOutputCallback,
+    <T::FrameType as IsFrameType>::Type: IsFrame,
+{
+    type FrameType = T::FrameType;
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        let mut result = self.callback.on_audio_ready(audio_stream, audio_data);
+
+        let ramp = audio_stream.duration_to_frames(self.ramp).max(1) as usize;
+        let stopping = self.control.is_stopping();
+
+        for frame in audio_data.iter_mut() {
+            let mut gain = 1.0;
+
+            if self.fade_in < ramp {
+                gain *= self.curve.gain(self.fade_in as f32 / ramp as f32);
+                self.fade_in += 1;
+            }
+
+            if stopping {
+                gain *= self
+                    .curve
+                    .gain(1.0 - (self.fade_out.min(ramp) as f32 / ramp as f32));
+                self.fade_out += 1;
+            }
+
+            if gain < 1.0 {
+                for channel in 0..<<Self::FrameType as IsFrameType>::Type as IsFrame>::CHANNELS {
+                    let sample = frame.channel(channel);
+                    frame.set_channel(channel, IsSample::from_f32(sample.to_f32() * gain));
+                }
+            }
+        }
+
+        if stopping && self.fade_out >= ramp {
+            self.fade_in = 0;
+            self.fade_out = 0;
+            self.control.stop.store(false, Ordering::Release);
+            result = DataCallbackResult::Stop;
+        }
+
+        result
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    marker::PhantomData,
+    slice::from_raw_parts_mut,
+    sync::{atomic::Ordering, Arc},
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    AudioOutputCallback, AudioOutputStreamSafe, AudioStreamBase, DataCallbackResult, Error,
+    IsFrameType, IsSample, Mono, Resampler, Result, Stereo,
+};
+
+use super::sample_ring::SampleFeed;
+
+const WORKER_CHUNK_FRAMES: usize = 256;
+
+/**
+ * The output callback which plays the interleaved samples of iterator
+ *
+ * The iterator is pulled on a worker thread into an internal ring buffer, so the data
+ * callback never blocks, even when the iterator decodes audio.
+ *
+ * The samples are converted to the channel count and the sample rate of stream, so any
+ * source can be played. The mono source is duplicated to both channels, while the channels
+ * of source beyond the first two are dropped. With `rodio` feature, the `rodio` sources can
+ * be played using `from_rodio`, which takes their channel count and sample rate.
+ *
+ * The source is resampled to the sample rate requested by builder. When the stream is opened
+ * with another sample rate, the callback outputs silence and returns
+ * [`DataCallbackResult::Stop`].
+ *
+ * When the iterator ends, the rest of buffered audio is played, then the callback returns
+ * [`DataCallbackResult::Stop`]. Buffer underruns are filled by silence.
+ */
+pub struct IteratorSource<F: IsFrameType> {
+    shared: Arc<SampleFeed<F::Format>>,
+    worker: Option<JoinHandle<()>>,
+    sample_rate: i32,
+    _phantom: PhantomData<F>,
+}
+
+impl<F> IteratorSource<F>
+where
+    F: IsFrameType,
+    F::Format: IsSample,
+{
+    fn spawn<B, I>(
+        builder: &B,
+        source: I,
+        source_channels: usize,
+        source_rate: i32,
+        channels: usize,
+        buffer_frames: usize,
+    ) -> Result<Self>
+    where
+        B: AudioStreamBase,
+        I: Iterator<Item = f32> + Send + 'static,
+    {
+        if source_channels == 0 {
+            return Err(Error::InvalidFormat);
+        }
+
+        let sample_rate = builder.get_sample_rate();
+        if sample_rate <= 0 || source_rate <= 0 {
+            return Err(Error::InvalidRate);
+        }
+
+        let resampler = if source_rate != sample_rate {
+            Some(Resampler::new(source_rate, sample_rate))
+        } else {
+            None
+        };
+
+        let shared = Arc::new(SampleFeed::new(buffer_frames.max(1) * channels));
+
+        let worker = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                source_worker(&shared, source, source_channels, resampler, channels);
+                shared.finished.store(true, Ordering::Release);
+            })
+        };
+
+        Ok(Self {
+            shared,
+            worker: Some(worker),
+            sample_rate,
+            _phantom: PhantomData,
+        })
+    }
+
+    /**
+     * Check that the iterator ended and all buffered audio was played
+     */
+    pub fn is_finished(&self) -> bool {
+        self.shared.is_finished()
+    }
+
+    fn render(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        samples: &mut [F::Format],
+    ) -> DataCallbackResult {
+        // The rate of opened stream may differ from the requested one
+        if audio_stream.get_sample_rate() != self.sample_rate {
+            for sample in samples.iter_mut() {
+                *sample = F::Format::SILENCE;
+            }
+            return DataCallbackResult::Stop;
+        }
+
+        if self.shared.render(samples) {
+            DataCallbackResult::Continue
+        } else {
+            DataCallbackResult::Stop
+        }
+    }
+}
+
+impl<T: IsSample> IteratorSource<(T, Mono)> {
+    /**
+     * Start pulling the iterator for mono stream
+     *
+     * The `source` yields interleaved samples of `channels` at `sample_rate`.
+     * The `buffer_frames` is the capacity of the internal buffer.
+     * Returns [`Error::InvalidRate`] when the sample rate of builder is unspecified.
+     */
+    pub fn new<B, I>(
+        builder: &B,
+        source: I,
+        channels: usize,
+        sample_rate: i32,
+        buffer_frames: usize,
+    ) -> Result<Self>
+    where
+        B: AudioStreamBase,
+        I: Iterator<Item = f32> + Send + 'static,
+    {
+        Self::spawn(builder, source, channels, sample_rate, 1, buffer_frames)
+    }
+}
+
+impl<T: IsSample> IteratorSource<(T, Stereo)> {
+    /**
+     * Start pulling the iterator for stereo stream
+     *
+     * The `source` yields interleaved samples of `channels` at `sample_rate`.
+     * The `buffer_frames` is the capacity of the internal buffer.
+     * Returns [`Error::InvalidRate`] when the sample rate of builder is unspecified.
+     */
+    pub fn new<B, I>(
+        builder: &B,
+        source: I,
+        channels: usize,
+        sample_rate: i32,
+        buffer_frames: usize,
+    ) -> Result<Self>
+    where
+        B: AudioStreamBase,
+        I: Iterator<Item = f32> + Send + 'static,
+    {
+        Self::spawn(builder, source, channels, sample_rate, 2, buffer_frames)
+    }
+}
+
+impl<F: IsFrameType> Drop for IteratorSource<F> {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Release);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<T: IsSample> AudioOutputCallback for IteratorSource<(T, Mono)> {
+    type FrameType = (T, Mono);
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [T],
+    ) -> DataCallbackResult {
+        self.render(audio_stream, audio_data)
+    }
+}
+
+impl<T: IsSample> AudioOutputCallback for IteratorSource<(T, Stereo)> {
+    type FrameType = (T, Stereo);
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [(T, T)],
+    ) -> DataCallbackResult {
+        let samples =
+            unsafe { from_raw_parts_mut(audio_data.as_mut_ptr() as *mut T, audio_data.len() * 2) };
+        self.render(audio_stream, samples)
+    }
+}
+
+fn source_worker<T, I>(
+    shared: &SampleFeed<T>,
+    mut source: I,
+    source_channels: usize,
+    mut resampler: Option<Resampler<(f32, f32)>>,
+    channels: usize,
+) where
+    T: IsSample,
+    I: Iterator<Item = f32>,
+{
+    let mut frames = Vec::with_capacity(WORKER_CHUNK_FRAMES);
+    let mut resampled = Vec::new();
+    let mut samples = Vec::new();
+    let mut frame = vec![0.0; source_channels];
+
+    while !shared.is_stopped() {
+        frames.clear();
+        let mut ended = false;
+        while frames.len() < WORKER_CHUNK_FRAMES {
+            // The incomplete last frame is dropped
+            if frame.iter_mut().any(|sample| match source.next() {
+                Some(value) => {
+                    *sample = value;
+                    false
+                }
+                None => true,
+            }) {
+                ended = true;
+                break;
+            }
+            frames.push((frame[0], frame[source_channels.min(2) - 1]));
+        }
+
+        let frames = match &mut resampler {
+            Some(resampler) => {
+                resampled.clear();
+                resampler.process(&frames, &mut resampled);
+                &resampled
+            }
+            None => &frames,
+        };
+
+        samples.clear();
+        for &(left, right) in frames {
+            if channels == 1 {
+                samples.push(T::from_f32(left.mean(right)));
+            } else {
+                samples.push(T::from_f32(left));
+                samples.push(T::from_f32(right));
+            }
+        }
+
+        if !shared.push_all(&samples) || ended {
+            break;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-driver"))]
+mod tests {
+    use super::*;
+    use crate::{test_stream::TestStream, CallbackDriver};
+
+    #[test]
+    fn stops_at_negotiated_rate_mismatch() {
+        let mut builder = TestStream::new();
+        builder.sample_rate = 48000;
+        let source =
+            IteratorSource::<(f32, Mono)>::new(&builder, std::iter::repeat(0.5), 1, 48000, 4)
+                .unwrap();
+        let mut driver = CallbackDriver::output(source, 44100);
+        assert_eq!(driver.render(2), [0.0, 0.0]);
+        assert_eq!(driver.last_result(), Some(DataCallbackResult::Stop));
+    }
+}
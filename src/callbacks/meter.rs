@@ -0,0 +1,259 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    AudioInputCallback, AudioInputStreamSafe, AudioOutputCallback, AudioOutputStreamSafe,
+    AudioStreamSafe, DataCallbackResult, Error, IsFrame, IsFrameType, IsSample,
+};
+
+#[derive(Default)]
+struct MeterChannel {
+    rms: AtomicU32,
+    peak: AtomicU32,
+}
+
+/**
+ * The levels published by [`MeterCallback`]
+ *
+ * It can be read from any thread, e.g. the UI thread which draws the level meter.
+ * The levels are normalized, so the full scale is 1.0.
+ */
+#[derive(Clone)]
+pub struct Meter {
+    channels: Arc<[MeterChannel]>,
+}
+
+impl Meter {
+    /**
+     * Get the number of metered channels
+     */
+    pub fn channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    /**
+     * Get the RMS level of channel over the last callback
+     *
+     * Panics when the channel index is out of range.
+     */
+    pub fn rms(&self, channel: usize) -> f32 {
+        f32::from_bits(self.channels[channel].rms.load(Ordering::Relaxed))
+    }
+
+    /**
+     * Get the peak level of channel with hold and decay applied
+     *
+     * Panics when the channel index is out of range.
+     */
+    pub fn peak(&self, channel: usize) -> f32 {
+        f32::from_bits(self.channels[channel].peak.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct PeakState {
+    value: f32,
+    hold_left: i64,
+}
+
+/**
+ * The callback adapter which measures the levels of frames
+ *
+ * The RMS and the peak of each channel are computed over each callback and published
+ * using [`Meter`]. The peak is held for the specified time, then decays at the specified
+ * rate in decibels per second, which is what the peak meters usually display.
+ *
+ * It works for both directions: the input frames are measured as captured,
+ * the output frames are measured as rendered by the wrapped callback.
+ * The full-scale sine gives the RMS of about 0.707 and the peak of 1.0.
+ */
+pub struct MeterCallback<T> {
+    callback: T,
+    meter: Meter,
+    hold: Duration,
+    decay: f32,
+    peaks: Vec<PeakState>,
+}
+
+impl<T> MeterCallback<T> {
+    /**
+     * Wrap the callback with meter of `channels` channels
+     *
+     * The peak is held for `hold`, then decays by `decay_db_per_second` decibels per second.
+     */
+    fn with_channels(
+        callback: T,
+        channels: usize,
+        hold: Duration,
+        decay_db_per_second: f32,
+    ) -> Self {
+        Self {
+            callback,
+            meter: Meter {
+                channels: (0..channels).map(|_| MeterChannel::default()).collect(),
+            },
+            hold,
+            decay: decay_db_per_second.max(0.0),
+            peaks: vec![Default::default(); channels],
+        }
+    }
+
+    /**
+     * Get the meter to read the levels
+     */
+    pub fn meter(&self) -> Meter {
+        self.meter.clone()
+    }
+
+    fn measure<S, F>(&mut self, audio_stream: &S, frames: &[F])
+    where
+        S: AudioStreamSafe + ?Sized,
+        F: IsFrame,
+    {
+        if frames.is_empty() {
+            return;
+        }
+
+        let hold = audio_stream.duration_to_frames(self.hold);
+        let seconds = frames.len() as f32 / audio_stream.get_sample_rate().max(1) as f32;
+        let decay = 10f32.powf(-self.decay * seconds / 20.0);
+
+        for (index, (channel, peak)) in self
+            .meter
+            .channels
+            .iter()
+            .zip(self.peaks.iter_mut())
+            .enumerate()
+        {
+            let mut sum = 0.0;
+            let mut max = 0.0f32;
+
+            for frame in frames {
+                let sample = frame.channel(index).to_f32();
+                sum += sample * sample;
+                max = max.max(sample.abs());
+            }
+
+            if max >= peak.value {
+                peak.value = max;
+                peak.hold_left = hold;
+            } else if peak.hold_left > 0 {
+                peak.hold_left -= frames.len() as i64;
+            } else {
+                peak.value = (peak.value * decay).max(max);
+            }
+
+            let rms = (sum / frames.len() as f32).sqrt();
+            channel.rms.store(rms.to_bits(), Ordering::Relaxed);
+            channel.peak.store(peak.value.to_bits(), Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> MeterCallback<T>
+where
+    T: AudioInputCallback,
+    <T::FrameType as IsFrameType>::Type: IsFrame,
+{
+    /**
+     * Wrap the input callback to meter the captured frames
+     *
+     * The peak is held for `hold`, then decays by `decay_db_per_second` decibels per second.
+     */
+    pub fn input(callback: T, hold: Duration, decay_db_per_second: f32) -> Self {
+        Self::with_channels(
+            callback,
+            <<T::FrameType as IsFrameType>::Type as IsFrame>::CHANNELS,
+            hold,
+            decay_db_per_second,
+        )
+    }
+}
+
+impl<T> MeterCallback<T>
+where
+    T: AudioOutputCallback,
+    <T::FrameType as IsFrameType>::Type: IsFrame,
+{
+    /**
+     * Wrap the output callback to meter the rendered frames
+     *
+     * The peak is held for `hold`, then decays by `decay_db_per_second` decibels per second.
+     */
+    pub fn output(callback: T, hold: Duration, decay_db_per_second: f32) -> Self {
+        Self::with_channels(
+            callback,
+            <<T::FrameType as IsFrameType>::Type as IsFrame>::CHANNELS,
+            hold,
+            decay_db_per_second,
+        )
+    }
+}
+
+impl<T> AudioInputCallback for MeterCallback<T>
+where
+    T: AudioInputCallback,
+    <T::FrameType as IsFrameType>::Type: IsFrame,
+{
+    type FrameType = T::FrameType;
+
+    fn on_error_before_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: &[<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        self.measure(&*audio_stream, audio_data);
+        self.callback.on_audio_ready(audio_stream, audio_data)
+    }
+}
+
+impl<T> AudioOutputCallback for MeterCallback<T>
+where
+    T: AudioOutputCallback,
+    <T::FrameType as IsFrameType>::Type: IsFrame,
+{
+    type FrameType = T::FrameType;
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        let result = self.callback.on_audio_ready(audio_stream, audio_data);
+        self.measure(&*audio_stream, audio_data);
+        result
+    }
+}
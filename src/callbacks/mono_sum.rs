@@ -0,0 +1,138 @@
+use std::marker::PhantomData;
+
+use crate::{
+    AudioOutputCallback, AudioOutputStreamSafe, DataCallbackResult, Error, IsSample, Mono, Stereo,
+};
+
+/**
+ * The output callback adapter which plays the true mono on the stereo stream
+ *
+ * It works in one of two ways, depending on the frames rendered by the wrapped callback:
+ *
+ * - [`MonoSumCallback::duplicate`] wraps the mono callback, and writes each rendered
+ *   sample to both channels.
+ * - [`MonoSumCallback::sum`] wraps the stereo callback, and replaces both channels with
+ *   the mean of left and right, see [`IsSample::mean`]. The integer samples never overflow.
+ *
+ * Unlike the channel conversion of Oboe, the stream is always opened as stereo,
+ * so the downmix is done exactly this way on every device.
+ *
+ * The mono frames are rendered in place, so no intermediate buffer is allocated.
+ */
+pub struct MonoSumCallback<T, C> {
+    callback: T,
+    _phantom: PhantomData<C>,
+}
+
+impl<T> MonoSumCallback<T, Mono> {
+    /**
+     * Wrap the mono callback to duplicate its samples to both channels
+     */
+    pub fn duplicate(callback: T) -> Self {
+        Self {
+            callback,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> MonoSumCallback<T, Stereo> {
+    /**
+     * Wrap the stereo callback to sum its channels to mono
+     */
+    pub fn sum(callback: T) -> Self {
+        Self {
+            callback,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, S> AudioOutputCallback for MonoSumCallback<T, Mono>
+where
+    T: AudioOutputCallback<FrameType = (S, Mono)>,
+    S: IsSample,
+{
+    type FrameType = (S, Stereo);
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [(S, S)],
+    ) -> DataCallbackResult {
+        // The mono frames are rendered in place into the first half of buffer,
+        // then spread from the end, so no sample is overwritten before it is read.
+        // The stereo frames are interleaved samples, like the buffer passed by Oboe.
+        let frames = audio_data.len();
+        let samples = unsafe {
+            std::slice::from_raw_parts_mut(audio_data.as_mut_ptr() as *mut S, frames * 2)
+        };
+
+        let result = self
+            .callback
+            .on_audio_ready(audio_stream, &mut samples[..frames]);
+
+        for index in (0..frames).rev() {
+            let sample = samples[index];
+            samples[2 * index] = sample;
+            samples[2 * index + 1] = sample;
+        }
+
+        result
+    }
+}
+
+impl<T, S> AudioOutputCallback for MonoSumCallback<T, Stereo>
+where
+    T: AudioOutputCallback<FrameType = (S, Stereo)>,
+    S: IsSample,
+{
+    type FrameType = (S, Stereo);
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [(S, S)],
+    ) -> DataCallbackResult {
+        let result = self.callback.on_audio_ready(audio_stream, audio_data);
+
+        for frame in audio_data.iter_mut() {
+            let sample = frame.0.mean(frame.1);
+            *frame = (sample, sample);
+        }
+
+        result
+    }
+}
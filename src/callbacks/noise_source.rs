@@ -0,0 +1,129 @@
+use std::marker::PhantomData;
+
+use crate::{
+    AudioOutputCallback, AudioOutputStreamSafe, DataCallbackResult, IsFrame, IsFrameType, IsSample,
+};
+
+/**
+ * The number of rows of Voss-McCartney pink noise generator
+ */
+const PINK_ROWS: usize = 16;
+
+/**
+ * The seed which replaces zero, since xorshift state must not be zero
+ */
+const DEFAULT_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/**
+ * The spectrum of noise
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseColor {
+    /**
+     * Equal power per frequency
+     */
+    White,
+
+    /**
+     * Equal power per octave, i.e. -3 dB per octave
+     */
+    Pink,
+}
+
+/**
+ * The output callback which generates reproducible noise
+ *
+ * The noise is generated by xorshift random number generator, so the sources with
+ * the same color and seed produce identical frames regardless of how the frames are split
+ * into callbacks. The pink noise is shaped using the Voss-McCartney algorithm.
+ *
+ * All channels of frame get the same sample.
+ */
+pub struct NoiseSource<F> {
+    color: NoiseColor,
+    amplitude: f32,
+    state: u64,
+    counter: u32,
+    rows: [f32; PINK_ROWS],
+    _phantom: PhantomData<F>,
+}
+
+impl<F> NoiseSource<F> {
+    /**
+     * Create the source of noise with specified color and seed at full scale
+     */
+    pub fn new(color: NoiseColor, seed: u64) -> Self {
+        let mut source = Self {
+            color,
+            amplitude: 1.0,
+            state: if seed == 0 { DEFAULT_SEED } else { seed },
+            counter: 0,
+            rows: [0.0; PINK_ROWS],
+            _phantom: PhantomData,
+        };
+        for row in 0..PINK_ROWS {
+            source.rows[row] = source.white();
+        }
+        source
+    }
+
+    /**
+     * Set the peak amplitude in range `0.0..=1.0`
+     */
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = amplitude.clamp(0.0, 1.0);
+    }
+
+    /**
+     * Generate the next value of white noise in range `-1.0..1.0`
+     */
+    fn white(&mut self) -> f32 {
+        // xorshift64*
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let value = self.state.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        // 24 bits are exactly representable by f32
+        (value >> 40) as f32 / (1u32 << 23) as f32 - 1.0
+    }
+
+    fn pink(&mut self) -> f32 {
+        self.counter = self.counter.wrapping_add(1);
+        let row = self.counter.trailing_zeros() as usize;
+        if row < PINK_ROWS {
+            self.rows[row] = self.white();
+        }
+        let sum: f32 = self.rows.iter().sum::<f32>() + self.white();
+        sum / (PINK_ROWS + 1) as f32
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let value = match self.color {
+            NoiseColor::White => self.white(),
+            NoiseColor::Pink => self.pink(),
+        };
+        value * self.amplitude
+    }
+}
+
+impl<F> AudioOutputCallback for NoiseSource<F>
+where
+    F: IsFrameType,
+    F::Type: IsFrame,
+{
+    type FrameType = F;
+
+    fn on_audio_ready(
+        &mut self,
+        _audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [F::Type],
+    ) -> DataCallbackResult {
+        for frame in audio_data {
+            let sample = IsSample::from_f32(self.next_sample());
+            for channel in 0..<F::Type as IsFrame>::CHANNELS {
+                frame.set_channel(channel, sample);
+            }
+        }
+        DataCallbackResult::Continue
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    AudioOutputCallback, AudioOutputStreamSafe, AudioStreamBase, DataCallbackResult, Error,
+    IsFrameType, Result, Status, NANOS_PER_SECOND,
+};
+
+/**
+ * The shared presentation delay of [`PresentationDelayCallback`]
+ *
+ * It can be changed from any thread while the stream is running.
+ */
+#[derive(Debug, Clone)]
+pub struct PresentationDelay {
+    delay: Arc<AtomicI64>,
+    max_delay: Duration,
+}
+
+impl PresentationDelay {
+    /**
+     * Set the delay of presentation
+     *
+     * Presenting earlier is impossible, so the delay is never negative.
+     * Returns [`Error::OutOfRange`] when the delay exceeds the maximum delay of the callback.
+     */
+    pub fn set(&self, delay: Duration) -> Status {
+        if delay > self.max_delay {
+            return Err(Error::OutOfRange);
+        }
+        self.delay.store(delay.as_nanos() as i64, Ordering::Release);
+        Ok(())
+    }
+
+    /**
+     * Get the current delay of presentation
+     */
+    pub fn get(&self) -> Duration {
+        Duration::from_nanos(self.delay.load(Ordering::Acquire) as u64)
+    }
+
+    /**
+     * Get the maximum delay of presentation
+     */
+    pub fn max(&self) -> Duration {
+        self.max_delay
+    }
+}
+
+/**
+ * The output callback adapter which delays the presentation of rendered frames
+ *
+ * It is useful for lip-sync correction, when the audio should be nudged to match
+ * the display pipeline.
+ *
+ * Neither Oboe nor AAudio can shift the presentation of the stream, so the adapter
+ * passes the rendered frames through the delay line. The delay line is allocated
+ * for the maximum delay at creation time, so changing the delay does not allocate
+ * in the data callback. The silence is presented until the delay line is filled.
+ *
+ * Changing the delay while playing drops or repeats the part of audio,
+ * which may be audible.
+ */
+pub struct PresentationDelayCallback<T: AudioOutputCallback> {
+    callback: T,
+    delay: PresentationDelay,
+    line: Vec<<T::FrameType as IsFrameType>::Type>,
+    position: usize,
+}
+
+impl<T> PresentationDelayCallback<T>
+where
+    T: AudioOutputCallback,
+    <T::FrameType as IsFrameType>::Type: Copy + Default,
+{
+    /**
+     * Wrap the output callback with the delay line for up to `max_delay`
+     *
+     * Returns [`Error::InvalidRate`] when the sample rate of builder is unspecified, so set it using
+     * [`AudioStreamBuilder::set_sample_rate`](crate::AudioStreamBuilder::set_sample_rate)
+     * before creating the callback.
+     */
+    pub fn new<B: AudioStreamBase>(builder: &B, callback: T, max_delay: Duration) -> Result<Self> {
+        let sample_rate = builder.get_sample_rate();
+        if sample_rate <= 0 {
+            return Err(Error::InvalidRate);
+        }
+
+        let max_frames =
+            (max_delay.as_nanos() * sample_rate as u128 / NANOS_PER_SECOND as u128) as usize;
+
+        Ok(Self {
+            callback,
+            delay: PresentationDelay {
+                delay: Default::default(),
+                max_delay,
+            },
+            line: vec![Default::default(); max_frames + 1],
+            position: 0,
+        })
+    }
+
+    /**
+     * Get the delay to change it
+     */
+    pub fn delay(&self) -> PresentationDelay {
+        self.delay.clone()
+    }
+}
+
+impl<T> AudioOutputCallback for PresentationDelayCallback<T>
+where
+    T: AudioOutputCallback,
+    <T::FrameType as IsFrameType>::Type: Copy + Default,
+{
+    type FrameType = T::FrameType;
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        let result = self.callback.on_audio_ready(audio_stream, audio_data);
+
+        let length = self.line.len();
+        let delay =
+            (audio_stream.duration_to_frames(self.delay.get()).max(0) as usize).min(length - 1);
+
+        for frame in audio_data.iter_mut() {
+            self.line[self.position] = *frame;
+            *frame = self.line[(self.position + length - delay) % length];
+            self.position = (self.position + 1) % length;
+        }
+
+        result
+    }
+}
@@ -0,0 +1,59 @@
+use rodio::{Sample, Source};
+
+use crate::{AudioStreamBase, IsSample, IteratorSource, Mono, Result, Stereo};
+
+impl<T: IsSample> IteratorSource<(T, Mono)> {
+    /**
+     * Start playing `rodio` source on mono stream
+     *
+     * The channel count and the sample rate of source are taken from [`Source::channels`]
+     * and [`Source::sample_rate`] when it is started, see [`IteratorSource`] for conversion.
+     * Returns [`Error::InvalidRate`](crate::Error::InvalidRate) when the sample rate of builder
+     * is unspecified.
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rodio")))]
+    pub fn from_rodio<B, S>(builder: &B, source: S, buffer_frames: usize) -> Result<Self>
+    where
+        B: AudioStreamBase,
+        S: Source + Send + 'static,
+        S::Item: Sample,
+    {
+        let channels = source.channels() as usize;
+        let sample_rate = source.sample_rate() as i32;
+        Self::new(
+            builder,
+            source.map(Sample::to_f32),
+            channels,
+            sample_rate,
+            buffer_frames,
+        )
+    }
+}
+
+impl<T: IsSample> IteratorSource<(T, Stereo)> {
+    /**
+     * Start playing `rodio` source on stereo stream
+     *
+     * The channel count and the sample rate of source are taken from [`Source::channels`]
+     * and [`Source::sample_rate`] when it is started, see [`IteratorSource`] for conversion.
+     * Returns [`Error::InvalidRate`](crate::Error::InvalidRate) when the sample rate of builder
+     * is unspecified.
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rodio")))]
+    pub fn from_rodio<B, S>(builder: &B, source: S, buffer_frames: usize) -> Result<Self>
+    where
+        B: AudioStreamBase,
+        S: Source + Send + 'static,
+        S::Item: Sample,
+    {
+        let channels = source.channels() as usize;
+        let sample_rate = source.sample_rate() as i32;
+        Self::new(
+            builder,
+            source.map(Sample::to_f32),
+            channels,
+            sample_rate,
+            buffer_frames,
+        )
+    }
+}
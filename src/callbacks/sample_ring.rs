@@ -0,0 +1,123 @@
+use std::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+const WORKER_IDLE_TIME: Duration = Duration::from_millis(2);
+
+/**
+ * Single producer single consumer ring of samples
+ */
+pub(super) struct SampleRing<T> {
+    buffer: Box<[UnsafeCell<T>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+unsafe impl<T: Send> Sync for SampleRing<T> {}
+
+impl<T: Copy + Default> SampleRing<T> {
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            buffer: (0..capacity)
+                .map(|_| UnsafeCell::new(T::default()))
+                .collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.head
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.tail.load(Ordering::Acquire))
+    }
+
+    /// Must be called from the producer only
+    pub(super) fn push(&self, samples: &[T]) -> usize {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        let count = samples
+            .len()
+            .min(self.buffer.len() - head.wrapping_sub(tail));
+
+        for (index, sample) in samples[..count].iter().enumerate() {
+            let cell = &self.buffer[head.wrapping_add(index) % self.buffer.len()];
+            unsafe { *cell.get() = *sample };
+        }
+
+        self.head.store(head.wrapping_add(count), Ordering::Release);
+        count
+    }
+
+    /// Must be called from the consumer only
+    pub(super) fn pop(&self, samples: &mut [T]) -> usize {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        let count = samples.len().min(head.wrapping_sub(tail));
+
+        for (index, sample) in samples[..count].iter_mut().enumerate() {
+            let cell = &self.buffer[tail.wrapping_add(index) % self.buffer.len()];
+            *sample = unsafe { *cell.get() };
+        }
+
+        self.tail.store(tail.wrapping_add(count), Ordering::Release);
+        count
+    }
+}
+
+/**
+ * The state shared between the data callback and the worker which fills the ring
+ */
+pub(super) struct SampleFeed<T> {
+    pub(super) ring: SampleRing<T>,
+    pub(super) stop: AtomicBool,
+    pub(super) finished: AtomicBool,
+}
+
+impl<T: Copy + Default> SampleFeed<T> {
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            ring: SampleRing::new(capacity),
+            stop: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+        }
+    }
+
+    pub(super) fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Acquire)
+    }
+
+    /// Push all samples waiting for free space, returns false when the worker should stop
+    pub(super) fn push_all(&self, samples: &[T]) -> bool {
+        let mut pushed = 0;
+        while pushed < samples.len() {
+            if self.is_stopped() {
+                return false;
+            }
+            pushed += self.ring.push(&samples[pushed..]);
+            if pushed < samples.len() {
+                thread::sleep(WORKER_IDLE_TIME);
+            }
+        }
+        true
+    }
+
+    pub(super) fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire) && self.ring.len() == 0
+    }
+
+    /// Fill samples from the ring, the underrun is filled by silence
+    pub(super) fn render(&self, samples: &mut [T]) -> bool {
+        let finished = self.finished.load(Ordering::Acquire);
+        let count = self.ring.pop(samples);
+
+        for sample in &mut samples[count..] {
+            *sample = Default::default();
+        }
+
+        !(finished && count < samples.len())
+    }
+}
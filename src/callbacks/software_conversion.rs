@@ -0,0 +1,362 @@
+use std::{
+    ffi::c_void,
+    slice::{from_raw_parts, from_raw_parts_mut},
+};
+
+use crate::{
+    AudioFormat, AudioInputCallback, AudioInputStreamSafe, AudioOutputCallback,
+    AudioOutputStreamSafe, DataCallbackResult, Dither, Error, IsChannelCount, IsFrame, IsFrameType,
+    IsSample, NativeInputCallback, NativeOutputCallback,
+};
+
+/**
+ * The quality of conversion from float to integer samples
+ *
+ * The dithering is applied to 16-bit samples only, the deeper samples are rounded.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionQuality {
+    /**
+     * The samples are truncated toward zero, which is the cheapest
+     */
+    Truncate,
+
+    /**
+     * The samples are rounded to the nearest integer
+     */
+    Round,
+
+    /**
+     * The samples are dithered with noise shaping, see [`Dither`]
+     */
+    Dither,
+}
+
+/**
+ * The callback adapter which converts the format of frames in software
+ *
+ * It lets the callback work with float frames, while the stream is opened in the native
+ * format of device and the format conversion of Oboe is disabled. The negotiated format is
+ * read back from the stream, and the frames are converted from or to it in each callback.
+ * The output frames are converted with the chosen [`ConversionQuality`], the input frames
+ * are converted exactly. The float frames are passed through as is.
+ *
+ * Usually created using `set_software_format_conversion` of the builder. When the callback
+ * is set directly, the builder must use float format, because the frames are passed through.
+ *
+ * The intermediate buffer is allocated for the buffer capacity of stream when it is opened,
+ * so the callbacks do not allocate.
+ */
+pub struct SoftwareConversion<T, F> {
+    callback: T,
+    quality: ConversionQuality,
+    dither: Dither,
+    buffer: Vec<F>,
+}
+
+impl<T, F: IsFrame> SoftwareConversion<T, F> {
+    /**
+     * Wrap the callback which processes float frames
+     */
+    pub fn new(callback: T, quality: ConversionQuality) -> Self {
+        Self {
+            callback,
+            quality,
+            dither: Dither::new(F::CHANNELS),
+            buffer: Vec::new(),
+        }
+    }
+
+    fn buffer(&mut self, frames: usize) -> &mut [F] {
+        if self.buffer.len() < frames {
+            self.buffer.resize(frames, F::default());
+        }
+        &mut self.buffer[..frames]
+    }
+}
+
+impl<T, C, F> SoftwareConversion<T, F>
+where
+    T: AudioOutputCallback<FrameType = (f32, C)>,
+    C: IsChannelCount,
+    (f32, C): IsFrameType<Type = F>,
+    F: IsFrame<Sample = f32>,
+{
+    fn render<S: NativeSample>(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [S],
+    ) -> DataCallbackResult {
+        let quality = self.quality;
+        let frames = audio_data.len() / F::CHANNELS;
+        self.buffer(frames);
+        let Self {
+            callback,
+            dither,
+            buffer,
+            ..
+        } = self;
+        let buffer = &mut buffer[..frames];
+
+        let result = callback.on_audio_ready(audio_stream, buffer);
+
+        for (input, output) in buffer.iter().zip(audio_data.chunks_exact_mut(F::CHANNELS)) {
+            for (channel, output) in output.iter_mut().enumerate() {
+                *output = S::quantize(input.channel(channel), quality, dither);
+            }
+        }
+
+        result
+    }
+}
+
+impl<T, C, F> SoftwareConversion<T, F>
+where
+    T: AudioInputCallback<FrameType = (f32, C)>,
+    C: IsChannelCount,
+    (f32, C): IsFrameType<Type = F>,
+    F: IsFrame<Sample = f32>,
+{
+    fn capture<S: NativeSample>(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: &[S],
+    ) -> DataCallbackResult {
+        let frames = audio_data.len() / F::CHANNELS;
+        let buffer = self.buffer(frames);
+
+        for (input, output) in audio_data.chunks_exact(F::CHANNELS).zip(buffer.iter_mut()) {
+            for (channel, input) in input.iter().enumerate() {
+                output.set_channel(channel, input.to_f32());
+            }
+        }
+
+        let Self {
+            callback, buffer, ..
+        } = self;
+        callback.on_audio_ready(audio_stream, &buffer[..frames])
+    }
+}
+
+impl<T, C, F> AudioOutputCallback for SoftwareConversion<T, F>
+where
+    T: AudioOutputCallback<FrameType = (f32, C)>,
+    C: IsChannelCount,
+    (f32, C): IsFrameType<Type = F>,
+    F: IsFrame<Sample = f32>,
+{
+    type FrameType = (f32, C);
+
+    fn on_error_before_close(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        error: Error,
+    ) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioOutputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [F],
+    ) -> DataCallbackResult {
+        self.callback.on_audio_ready(audio_stream, audio_data)
+    }
+}
+
+impl<T, C, F> NativeOutputCallback for SoftwareConversion<T, F>
+where
+    T: AudioOutputCallback<FrameType = (f32, C)>,
+    C: IsChannelCount,
+    (f32, C): IsFrameType<Type = F>,
+    F: IsFrame<Sample = f32>,
+{
+    unsafe fn on_native_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: *mut c_void,
+        num_frames: usize,
+    ) -> DataCallbackResult {
+        let samples = num_frames * F::CHANNELS;
+        match audio_stream.get_format() {
+            AudioFormat::I16 => self.render(
+                audio_stream,
+                from_raw_parts_mut(audio_data as *mut i16, samples),
+            ),
+            AudioFormat::I24 => self.render(
+                audio_stream,
+                from_raw_parts_mut(audio_data as *mut [u8; 3], samples),
+            ),
+            AudioFormat::I32 => self.render(
+                audio_stream,
+                from_raw_parts_mut(audio_data as *mut i32, samples),
+            ),
+            AudioFormat::F32 => self.on_audio_ready(
+                audio_stream,
+                from_raw_parts_mut(audio_data as *mut F, num_frames),
+            ),
+            // The size of frames is unknown, so even the silence can't be written
+            _ => DataCallbackResult::Stop,
+        }
+    }
+
+    fn reserve_frames(&mut self, frames: usize) {
+        self.buffer(frames);
+    }
+}
+
+impl<T, C, F> AudioInputCallback for SoftwareConversion<T, F>
+where
+    T: AudioInputCallback<FrameType = (f32, C)>,
+    C: IsChannelCount,
+    (f32, C): IsFrameType<Type = F>,
+    F: IsFrame<Sample = f32>,
+{
+    type FrameType = (f32, C);
+
+    fn on_error_before_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        self.callback.on_error_before_close(audio_stream, error);
+    }
+
+    fn on_error_after_close(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, error: Error) {
+        self.callback.on_error_after_close(audio_stream, error);
+    }
+
+    fn on_xrun(&mut self, audio_stream: &mut dyn AudioInputStreamSafe, delta: i32) {
+        self.callback.on_xrun(audio_stream, delta);
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: &[F],
+    ) -> DataCallbackResult {
+        self.callback.on_audio_ready(audio_stream, audio_data)
+    }
+}
+
+impl<T, C, F> NativeInputCallback for SoftwareConversion<T, F>
+where
+    T: AudioInputCallback<FrameType = (f32, C)>,
+    C: IsChannelCount,
+    (f32, C): IsFrameType<Type = F>,
+    F: IsFrame<Sample = f32>,
+{
+    unsafe fn on_native_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: *const c_void,
+        num_frames: usize,
+    ) -> DataCallbackResult {
+        let samples = num_frames * F::CHANNELS;
+        match audio_stream.get_format() {
+            AudioFormat::I16 => self.capture(
+                audio_stream,
+                from_raw_parts(audio_data as *const i16, samples),
+            ),
+            AudioFormat::I24 => self.capture(
+                audio_stream,
+                from_raw_parts(audio_data as *const [u8; 3], samples),
+            ),
+            AudioFormat::I32 => self.capture(
+                audio_stream,
+                from_raw_parts(audio_data as *const i32, samples),
+            ),
+            AudioFormat::F32 => self.on_audio_ready(
+                audio_stream,
+                from_raw_parts(audio_data as *const F, num_frames),
+            ),
+            _ => DataCallbackResult::Stop,
+        }
+    }
+
+    fn reserve_frames(&mut self, frames: usize) {
+        self.buffer(frames);
+    }
+}
+
+/// The integer sample formats which the stream may be opened with
+trait NativeSample: Copy {
+    fn to_f32(self) -> f32;
+
+    fn quantize(sample: f32, quality: ConversionQuality, dither: &mut Dither) -> Self;
+}
+
+impl NativeSample for i16 {
+    fn to_f32(self) -> f32 {
+        IsSample::to_f32(self)
+    }
+
+    fn quantize(sample: f32, quality: ConversionQuality, dither: &mut Dither) -> Self {
+        match quality {
+            ConversionQuality::Truncate => IsSample::from_f32(sample),
+            ConversionQuality::Round => scale(sample, quality, -(i16::MIN as f64)) as i16,
+            ConversionQuality::Dither => {
+                let mut converted = [0i16];
+                dither.process(&[sample], &mut converted);
+                converted[0]
+            }
+        }
+    }
+}
+
+/// The packed little endian 24-bit sample
+impl NativeSample for [u8; 3] {
+    fn to_f32(self) -> f32 {
+        let [a, b, c] = self;
+        IsSample::to_f32(i32::from_le_bytes([0, a, b, c]))
+    }
+
+    fn quantize(sample: f32, quality: ConversionQuality, _dither: &mut Dither) -> Self {
+        let [a, b, c, _] = (scale(sample, quality, (1 << 23) as f64) as i32).to_le_bytes();
+        [a, b, c]
+    }
+}
+
+impl NativeSample for i32 {
+    fn to_f32(self) -> f32 {
+        IsSample::to_f32(self)
+    }
+
+    fn quantize(sample: f32, quality: ConversionQuality, _dither: &mut Dither) -> Self {
+        scale(sample, quality, -(i32::MIN as f64)) as i32
+    }
+}
+
+/// Scale the normalized sample by `full_scale` like [`IsSample::from_f32`] without dithering
+///
+/// The result is clipped to the range `-full_scale..full_scale`.
+fn scale(sample: f32, quality: ConversionQuality, full_scale: f64) -> f64 {
+    let scaled = (sample as f64 * full_scale).clamp(-full_scale, full_scale - 1.0);
+    match quality {
+        ConversionQuality::Truncate => scaled.trunc(),
+        ConversionQuality::Round | ConversionQuality::Dither => scaled.round(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_24_bit_round_trip() {
+        let mut dither = Dither::new(1);
+        for &sample in &[-1.0, -0.5, 0.0, 0.25, 0.999] {
+            let packed = <[u8; 3]>::quantize(sample, ConversionQuality::Round, &mut dither);
+            assert!((packed.to_f32() - sample).abs() < 1e-6);
+        }
+
+        let max = <[u8; 3]>::quantize(1.5, ConversionQuality::Round, &mut dither);
+        assert_eq!(max, [0xff, 0xff, 0x7f]);
+        let min = <[u8; 3]>::quantize(-1.5, ConversionQuality::Round, &mut dither);
+        assert_eq!(min, [0x00, 0x00, 0x80]);
+    }
+}
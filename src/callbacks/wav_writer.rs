@@ -0,0 +1,200 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    mem::size_of_val,
+    path::Path,
+    slice::from_raw_parts,
+};
+
+use crate::{
+    AudioFormat, AudioInputCallback, AudioInputStreamSafe, DataCallbackResult, Error, IsFormat,
+    IsFrameType, Mono, Stereo,
+};
+
+const HEADER_SIZE: u32 = 44;
+const FORMAT_PCM: u16 = 1;
+const FORMAT_IEEE_FLOAT: u16 = 3;
+
+/**
+ * The input callback which writes captured audio to WAV file
+ *
+ * The header is written on the first data callback using the negotiated sample rate,
+ * so the stream parameters need not be known at construction time.
+ *
+ * The sizes in header are updated when the callback is dropped, i.e. when the stream is
+ * closed, or when an error occurs on the stream.
+ *
+ * Note that file I/O in the data callback is not real-time safe, so consider using
+ * [`PerformanceMode::PowerSaving`](crate::PerformanceMode::PowerSaving) or
+ * no specific mode for recording.
+ * On I/O error the callback returns [`DataCallbackResult::Stop`].
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "wav")))]
+pub struct WavWriterCallback<W: Write + Seek, F> {
+    writer: W,
+    header_written: bool,
+    data_size: u32,
+    _phantom: PhantomData<F>,
+}
+
+impl<F> WavWriterCallback<BufWriter<File>, F> {
+    /**
+     * Create WAV file at specified path
+     */
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        File::create(path).map(|file| Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write + Seek, F> WavWriterCallback<W, F> {
+    /**
+     * Create callback which writes WAV data into the writer
+     */
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+            data_size: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /**
+     * Get the number of bytes of audio data which was written
+     */
+    pub fn data_size(&self) -> u32 {
+        self.data_size
+    }
+
+    fn write_header(
+        &mut self,
+        format: AudioFormat,
+        channels: u16,
+        sample_rate: u32,
+    ) -> io::Result<()> {
+        let (format_tag, bits_per_sample) = match format {
+            AudioFormat::I16 => (FORMAT_PCM, 16u16),
+            AudioFormat::I32 => (FORMAT_PCM, 32),
+            AudioFormat::F32 => (FORMAT_IEEE_FLOAT, 32),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Unsupported audio format",
+                ))
+            }
+        };
+        let block_align = channels * bits_per_sample / 8;
+        let byte_rate = sample_rate * block_align as u32;
+
+        let w = &mut self.writer;
+        w.write_all(b"RIFF")?;
+        w.write_all(&(HEADER_SIZE - 8).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+        w.write_all(b"fmt ")?;
+        w.write_all(&16u32.to_le_bytes())?;
+        w.write_all(&format_tag.to_le_bytes())?;
+        w.write_all(&channels.to_le_bytes())?;
+        w.write_all(&sample_rate.to_le_bytes())?;
+        w.write_all(&byte_rate.to_le_bytes())?;
+        w.write_all(&block_align.to_le_bytes())?;
+        w.write_all(&bits_per_sample.to_le_bytes())?;
+        w.write_all(b"data")?;
+        w.write_all(&0u32.to_le_bytes())?;
+
+        self.header_written = true;
+        Ok(())
+    }
+
+    fn write_frames<T>(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        format: AudioFormat,
+        channels: u16,
+        frames: &[T],
+    ) -> io::Result<()> {
+        if !self.header_written {
+            self.write_header(format, channels, audio_stream.get_sample_rate() as u32)?;
+        }
+
+        // Android targets are little-endian so the samples can be written as is
+        let bytes = unsafe { from_raw_parts(frames.as_ptr() as *const u8, size_of_val(frames)) };
+        self.writer.write_all(bytes)?;
+        self.data_size = self.data_size.saturating_add(bytes.len() as u32);
+        Ok(())
+    }
+
+    /**
+     * Update the sizes in header and flush the writer
+     *
+     * It is called automatically on drop.
+     */
+    pub fn finalize(&mut self) -> io::Result<()> {
+        if !self.header_written {
+            return self.writer.flush();
+        }
+
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(
+            &(HEADER_SIZE - 8)
+                .saturating_add(self.data_size)
+                .to_le_bytes(),
+        )?;
+        self.writer.seek(SeekFrom::Start(HEADER_SIZE as u64 - 4))?;
+        self.writer.write_all(&self.data_size.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()
+    }
+}
+
+impl<W: Write + Seek, F> Drop for WavWriterCallback<W, F> {
+    fn drop(&mut self) {
+        let _ = self.finalize();
+    }
+}
+
+impl<W: Write + Seek, T: IsFormat> AudioInputCallback for WavWriterCallback<W, (T, Mono)> {
+    type FrameType = (T, Mono);
+
+    fn on_error_before_close(
+        &mut self,
+        _audio_stream: &mut dyn AudioInputStreamSafe,
+        _error: Error,
+    ) {
+        let _ = self.finalize();
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: &[<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        match self.write_frames(audio_stream, T::FORMAT, 1, audio_data) {
+            Ok(_) => DataCallbackResult::Continue,
+            Err(_) => DataCallbackResult::Stop,
+        }
+    }
+}
+
+impl<W: Write + Seek, T: IsFormat> AudioInputCallback for WavWriterCallback<W, (T, Stereo)> {
+    type FrameType = (T, Stereo);
+
+    fn on_error_before_close(
+        &mut self,
+        _audio_stream: &mut dyn AudioInputStreamSafe,
+        _error: Error,
+    ) {
+        let _ = self.finalize();
+    }
+
+    fn on_audio_ready(
+        &mut self,
+        audio_stream: &mut dyn AudioInputStreamSafe,
+        audio_data: &[<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        match self.write_frames(audio_stream, T::FORMAT, 2, audio_data) {
+            Ok(_) => DataCallbackResult::Continue,
+            Err(_) => DataCallbackResult::Stop,
+        }
+    }
+}
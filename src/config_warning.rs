@@ -0,0 +1,137 @@
+use std::fmt::{self, Display};
+
+use super::{AudioApi, AudioStreamBase, PerformanceMode, SampleRateConversionQuality, SharingMode};
+
+/**
+ * The buffer capacity above which the low latency mode is pointless
+ */
+const LOW_LATENCY_MAX_CAPACITY_MILLIS: i64 = 100;
+
+/**
+ * The sample rate assumed when the requested one is unspecified
+ */
+const ASSUMED_SAMPLE_RATE: i64 = 48000;
+
+/**
+ * The range of sample rates which the audio devices actually use
+ */
+pub(crate) const SAMPLE_RATE_RANGE: std::ops::RangeInclusive<i32> = 8000..=384000;
+
+/**
+ * The contradictory combination of builder settings
+ *
+ * See [`AudioStreamBuilder::validate`](crate::AudioStreamBuilder::validate).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigWarning {
+    /**
+     * The exclusive mode is requested without the low latency performance mode,
+     * but the exclusive MMAP path is used only for low latency streams.
+     */
+    ExclusiveWithoutLowLatency,
+
+    /**
+     * The exclusive mode is requested with OpenSL ES, which supports only the shared mode.
+     */
+    ExclusiveWithOpenSLES,
+
+    /**
+     * The buffer capacity in frames is too big for the low latency performance mode.
+     */
+    LargeBufferWithLowLatency(i32),
+
+    /**
+     * The frames per callback exceeds the buffer capacity.
+     */
+    CallbackExceedsBuffer(i32, i32),
+
+    /**
+     * The sample rate conversion quality is set, but the sample rate is unspecified,
+     * so there is nothing to convert to.
+     */
+    ConversionWithoutSampleRate,
+
+    /**
+     * The sample rate is out of the range from 8000 to 384000 Hz,
+     * which is most likely a typo.
+     */
+    SampleRateOutOfRange(i32),
+}
+
+impl Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigWarning::ExclusiveWithoutLowLatency => {
+                "Exclusive sharing mode without low latency performance mode".fmt(f)
+            }
+            ConfigWarning::ExclusiveWithOpenSLES => "Exclusive sharing mode with OpenSL ES".fmt(f),
+            ConfigWarning::LargeBufferWithLowLatency(capacity) => write!(
+                f,
+                "Buffer capacity of {} frames with low latency performance mode",
+                capacity
+            ),
+            ConfigWarning::CallbackExceedsBuffer(frames, capacity) => write!(
+                f,
+                "Frames per callback {} exceeds buffer capacity {}",
+                frames, capacity
+            ),
+            ConfigWarning::ConversionWithoutSampleRate => {
+                "Sample rate conversion quality without sample rate".fmt(f)
+            }
+            ConfigWarning::SampleRateOutOfRange(sample_rate) => {
+                write!(f, "Sample rate {} Hz is out of range", sample_rate)
+            }
+        }
+    }
+}
+
+pub(crate) fn validate_config<T: AudioStreamBase>(
+    base: &T,
+    audio_api: AudioApi,
+) -> Vec<ConfigWarning> {
+    let mut warnings = Vec::new();
+
+    let low_latency = base.get_performance_mode() == PerformanceMode::LowLatency;
+    let capacity = base.get_buffer_capacity_in_frames();
+    let frames_per_callback = base.get_frames_per_callback();
+    let sample_rate = base.get_sample_rate();
+
+    if base.get_sharing_mode() == SharingMode::Exclusive {
+        if !low_latency {
+            warnings.push(ConfigWarning::ExclusiveWithoutLowLatency);
+        }
+        if audio_api == AudioApi::OpenSLES {
+            warnings.push(ConfigWarning::ExclusiveWithOpenSLES);
+        }
+    }
+
+    if low_latency && capacity > 0 {
+        let rate = if sample_rate > 0 {
+            sample_rate as i64
+        } else {
+            ASSUMED_SAMPLE_RATE
+        };
+        if capacity as i64 * 1000 > rate * LOW_LATENCY_MAX_CAPACITY_MILLIS {
+            warnings.push(ConfigWarning::LargeBufferWithLowLatency(capacity));
+        }
+    }
+
+    if capacity > 0 && frames_per_callback > capacity {
+        warnings.push(ConfigWarning::CallbackExceedsBuffer(
+            frames_per_callback,
+            capacity,
+        ));
+    }
+
+    if sample_rate <= 0
+        && base.get_sample_rate_conversion_quality() != SampleRateConversionQuality::None
+    {
+        warnings.push(ConfigWarning::ConversionWithoutSampleRate);
+    }
+
+    if sample_rate > 0 && !SAMPLE_RATE_RANGE.contains(&sample_rate) {
+        warnings.push(ConfigWarning::SampleRateOutOfRange(sample_rate));
+    }
+
+    warnings
+}
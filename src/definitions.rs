@@ -1,5 +1,4 @@
 use num_derive::{FromPrimitive, ToPrimitive};
-use num_traits::FromPrimitive;
 use oboe_sys as ffi;
 use std::{error, fmt, result};
 
@@ -99,6 +98,23 @@ pub enum AudioFormat {
     F32 = ffi::oboe_AudioFormat_Float,
 }
 
+impl AudioFormat {
+    /**
+     * Get the number of bytes per sample of format
+     *
+     * The 24-bit integers are packed, so they take 3 bytes.
+     * Returns 0 for the invalid and unspecified formats.
+     */
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            AudioFormat::I16 => 2,
+            AudioFormat::I24 => 3,
+            AudioFormat::I32 | AudioFormat::F32 => 4,
+            AudioFormat::Invalid | AudioFormat::Unspecified => 0,
+        }
+    }
+}
+
 /**
  * The result of an audio callback.
  */
@@ -118,6 +134,9 @@ pub enum DataCallbackResult {
 
 /**
  * The result of an operation with value
+ *
+ * The [`Error`] implements [`std::error::Error`], so it can be converted into
+ * `Box<dyn std::error::Error>` by the `?` operator.
  */
 pub type Result<T> = result::Result<T, Error>;
 
@@ -130,7 +149,7 @@ pub(crate) fn wrap_status(result: i32) -> Status {
     if result == ffi::oboe_Result_OK {
         Ok(())
     } else {
-        Err(FromPrimitive::from_i32(result).unwrap())
+        Err(Error::from_code(result))
     }
 }
 
@@ -138,33 +157,118 @@ pub(crate) fn wrap_result<T>(result: ffi::oboe_ResultWithValue<T>) -> Result<T>
     if result.mError == ffi::oboe_Result_OK {
         Ok(result.mValue)
     } else {
-        Err(FromPrimitive::from_i32(result.mError).unwrap())
+        Err(Error::from_code(result.mError))
     }
 }
 
 /**
  * The error of an operation.
+ *
+ * Use [`Error::code`] to get the raw Oboe result code, e.g. for telemetry.
  */
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
-    Disconnected = ffi::oboe_Result_ErrorDisconnected,
-    IllegalArgument = ffi::oboe_Result_ErrorIllegalArgument,
-    Internal = ffi::oboe_Result_ErrorInternal,
-    InvalidState = ffi::oboe_Result_ErrorInvalidState,
-    InvalidHandle = ffi::oboe_Result_ErrorInvalidHandle,
-    Unimplemented = ffi::oboe_Result_ErrorUnimplemented,
-    Unavailable = ffi::oboe_Result_ErrorUnavailable,
-    NoFreeHandles = ffi::oboe_Result_ErrorNoFreeHandles,
-    NoMemory = ffi::oboe_Result_ErrorNoMemory,
-    Null = ffi::oboe_Result_ErrorNull,
-    Timeout = ffi::oboe_Result_ErrorTimeout,
-    WouldBlock = ffi::oboe_Result_ErrorWouldBlock,
-    InvalidFormat = ffi::oboe_Result_ErrorInvalidFormat,
-    OutOfRange = ffi::oboe_Result_ErrorOutOfRange,
-    NoService = ffi::oboe_Result_ErrorNoService,
-    InvalidRate = ffi::oboe_Result_ErrorInvalidRate,
-    Closed = ffi::oboe_Result_ErrorClosed,
+    Disconnected,
+    IllegalArgument,
+    Internal,
+    InvalidState,
+    InvalidHandle,
+    Unimplemented,
+    /**
+     * The requested resource is unavailable, e.g. no more low latency streams can be opened
+     */
+    Unavailable,
+    /**
+     * The system is out of stream handles, e.g. too many exclusive streams are open
+     */
+    NoFreeHandles,
+    NoMemory,
+    Null,
+    Timeout,
+    WouldBlock,
+    InvalidFormat,
+    OutOfRange,
+    NoService,
+    InvalidRate,
+    Closed,
+    /**
+     * The result code which is not known to this crate, e.g. added in a newer Oboe
+     */
+    Unknown(i32),
+}
+
+impl Error {
+    /**
+     * Convert the raw Oboe result code of failure to the error
+     *
+     * The unrecognized codes are kept in [`Error::Unknown`].
+     */
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            ffi::oboe_Result_ErrorDisconnected => Error::Disconnected,
+            ffi::oboe_Result_ErrorIllegalArgument => Error::IllegalArgument,
+            ffi::oboe_Result_ErrorInternal => Error::Internal,
+            ffi::oboe_Result_ErrorInvalidState => Error::InvalidState,
+            ffi::oboe_Result_ErrorInvalidHandle => Error::InvalidHandle,
+            ffi::oboe_Result_ErrorUnimplemented => Error::Unimplemented,
+            ffi::oboe_Result_ErrorUnavailable => Error::Unavailable,
+            ffi::oboe_Result_ErrorNoFreeHandles => Error::NoFreeHandles,
+            ffi::oboe_Result_ErrorNoMemory => Error::NoMemory,
+            ffi::oboe_Result_ErrorNull => Error::Null,
+            ffi::oboe_Result_ErrorTimeout => Error::Timeout,
+            ffi::oboe_Result_ErrorWouldBlock => Error::WouldBlock,
+            ffi::oboe_Result_ErrorInvalidFormat => Error::InvalidFormat,
+            ffi::oboe_Result_ErrorOutOfRange => Error::OutOfRange,
+            ffi::oboe_Result_ErrorNoService => Error::NoService,
+            ffi::oboe_Result_ErrorInvalidRate => Error::InvalidRate,
+            ffi::oboe_Result_ErrorClosed => Error::Closed,
+            code => Error::Unknown(code),
+        }
+    }
+
+    /**
+     * Get the raw Oboe result code of the error
+     */
+    pub fn code(&self) -> i32 {
+        match *self {
+            Error::Disconnected => ffi::oboe_Result_ErrorDisconnected,
+            Error::IllegalArgument => ffi::oboe_Result_ErrorIllegalArgument,
+            Error::Internal => ffi::oboe_Result_ErrorInternal,
+            Error::InvalidState => ffi::oboe_Result_ErrorInvalidState,
+            Error::InvalidHandle => ffi::oboe_Result_ErrorInvalidHandle,
+            Error::Unimplemented => ffi::oboe_Result_ErrorUnimplemented,
+            Error::Unavailable => ffi::oboe_Result_ErrorUnavailable,
+            Error::NoFreeHandles => ffi::oboe_Result_ErrorNoFreeHandles,
+            Error::NoMemory => ffi::oboe_Result_ErrorNoMemory,
+            Error::Null => ffi::oboe_Result_ErrorNull,
+            Error::Timeout => ffi::oboe_Result_ErrorTimeout,
+            Error::WouldBlock => ffi::oboe_Result_ErrorWouldBlock,
+            Error::InvalidFormat => ffi::oboe_Result_ErrorInvalidFormat,
+            Error::OutOfRange => ffi::oboe_Result_ErrorOutOfRange,
+            Error::NoService => ffi::oboe_Result_ErrorNoService,
+            Error::InvalidRate => ffi::oboe_Result_ErrorInvalidRate,
+            Error::Closed => ffi::oboe_Result_ErrorClosed,
+            Error::Unknown(code) => code,
+        }
+    }
+
+    /**
+     * Get the AAudio result code of the error, e.g. for reporting to the device vendors
+     *
+     * Oboe defines its result codes equal to the AAudio ones, so the code maps to
+     * the `AAUDIO_ERROR_*` constant with the same name: from `AAUDIO_ERROR_DISCONNECTED` (-899)
+     * to `AAUDIO_ERROR_INVALID_RATE` (-880). The [`Error::Closed`] is specific to Oboe,
+     * so it gives `None`.
+     *
+     * It is meaningful only when the stream used AAudio: the errors of OpenSL ES are
+     * translated by Oboe to the nearest result, so the original code is lost.
+     */
+    pub fn as_aaudio_result(&self) -> Option<i32> {
+        match self {
+            Error::Closed => None,
+            _ => Some(self.code()),
+        }
+    }
 }
 
 impl error::Error for Error {}
@@ -222,6 +326,64 @@ pub enum PerformanceMode {
     LowLatency = ffi::oboe_PerformanceMode_LowLatency,
 }
 
+impl PerformanceMode {
+    /**
+     * Get all performance modes in order of declaration, e.g. to list them in settings
+     */
+    pub fn all() -> &'static [Self] {
+        &[
+            PerformanceMode::None,
+            PerformanceMode::PowerSaving,
+            PerformanceMode::LowLatency,
+        ]
+    }
+}
+
+/**
+ * The class of buffer latency.
+ *
+ * See [`AudioStreamSafe::set_latency_class`](crate::AudioStreamSafe::set_latency_class).
+ *
+ * The buffer size is set to the fixed number of bursts, so the behavior is predictable
+ * across devices. The longer buffer tolerates more scheduling jitter at the cost of latency.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatencyClass {
+    /**
+     * The buffer of 2 bursts, e.g. for games and instruments.
+     */
+    UltraLow,
+
+    /**
+     * The buffer of 3 bursts.
+     */
+    Low,
+
+    /**
+     * The buffer of 4 bursts.
+     */
+    Balanced,
+
+    /**
+     * The buffer of 8 bursts, e.g. for background music.
+     */
+    Robust,
+}
+
+impl LatencyClass {
+    /**
+     * Get the size of buffer in bursts
+     */
+    pub fn bursts(self) -> i32 {
+        match self {
+            LatencyClass::UltraLow => 2,
+            LatencyClass::Low => 3,
+            LatencyClass::Balanced => 4,
+            LatencyClass::Robust => 8,
+        }
+    }
+}
+
 /**
  * The underlying audio API used by the audio stream.
  */
@@ -272,6 +434,22 @@ pub enum SampleRateConversionQuality {
     Best,
 }
 
+impl SampleRateConversionQuality {
+    /**
+     * Get all conversion qualities in order of declaration, e.g. to list them in settings
+     */
+    pub fn all() -> &'static [Self] {
+        &[
+            SampleRateConversionQuality::None,
+            SampleRateConversionQuality::Fastest,
+            SampleRateConversionQuality::Low,
+            SampleRateConversionQuality::Medium,
+            SampleRateConversionQuality::High,
+            SampleRateConversionQuality::Best,
+        ]
+    }
+}
+
 /**
  * The Usage attribute expresses *why* you are playing a sound, what is this sound used for.
  * This information is used by certain platforms or routing policies
@@ -346,6 +524,28 @@ pub enum Usage {
     Assistant = ffi::oboe_Usage_Assistant,
 }
 
+impl Usage {
+    /**
+     * Get all usages in order of declaration, e.g. to list them in settings
+     */
+    pub fn all() -> &'static [Self] {
+        &[
+            Usage::Media,
+            Usage::VoiceCommunication,
+            Usage::VoiceCommunicationSignalling,
+            Usage::Alarm,
+            Usage::Notification,
+            Usage::NotificationRingtone,
+            Usage::NotificationEvent,
+            Usage::AssistanceAccessibility,
+            Usage::AssistanceNavigationGuidance,
+            Usage::AssistanceSonification,
+            Usage::Game,
+            Usage::Assistant,
+        ]
+    }
+}
+
 /**
  * The ContentType attribute describes *what* you are playing.
  * It expresses the general category of the content. This information is optional.
@@ -383,6 +583,20 @@ pub enum ContentType {
     Sonification = ffi::oboe_ContentType_Sonification,
 }
 
+impl ContentType {
+    /**
+     * Get all content types in order of declaration, e.g. to list them in settings
+     */
+    pub fn all() -> &'static [Self] {
+        &[
+            ContentType::Speech,
+            ContentType::Music,
+            ContentType::Movie,
+            ContentType::Sonification,
+        ]
+    }
+}
+
 /**
  * Defines the audio source.
  * An audio source defines both a default physical source of audio signal, and a recording
@@ -419,6 +633,8 @@ pub enum InputPreset {
      * Use this preset to obtain an input with no effects.
      * Note that this input will not have automatic gain control
      * so the recorded volume may be very low.
+     *
+     * Requires Android API 24+ (`MediaRecorder.AudioSource.UNPROCESSED`).
      */
     Unprocessed = ffi::oboe_InputPreset_Unprocessed,
 
@@ -426,10 +642,36 @@ pub enum InputPreset {
      * Use this preset for capturing audio meant to be processed in real time
      * and played back for live performance (e.g karaoke).
      * The capture path will minimize latency and coupling with playback path.
+     *
+     * Requires Android API 29+ (`MediaRecorder.AudioSource.VOICE_PERFORMANCE`).
      */
     VoicePerformance = ffi::oboe_InputPreset_VoicePerformance,
 }
 
+// The presets must keep the values of `MediaRecorder.AudioSource` when the bindings are regenerated
+const _: () = assert!(
+    InputPreset::Unprocessed as i32 == ffi::oboe_InputPreset_Unprocessed
+        && ffi::oboe_InputPreset_Unprocessed == 9
+        && InputPreset::VoicePerformance as i32 == ffi::oboe_InputPreset_VoicePerformance
+        && ffi::oboe_InputPreset_VoicePerformance == 10
+);
+
+impl InputPreset {
+    /**
+     * Get all input presets in order of declaration, e.g. to list them in settings
+     */
+    pub fn all() -> &'static [Self] {
+        &[
+            InputPreset::Generic,
+            InputPreset::Camcorder,
+            InputPreset::VoiceRecognition,
+            InputPreset::VoiceCommunication,
+            InputPreset::Unprocessed,
+            InputPreset::VoicePerformance,
+        ]
+    }
+}
+
 /**
  * This attribute can be used to allocate a session ID to the audio stream.
  *
@@ -547,6 +789,25 @@ impl DefaultStreamValues {
     }
 }
 
+/**
+ * The clock which the timestamps are taken from
+ *
+ * See [`AudioStreamSafe::get_timestamp`](crate::AudioStreamSafe::get_timestamp).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum ClockId {
+    /**
+     * The clock which does not advance while the system is suspended, `CLOCK_MONOTONIC`
+     */
+    Monotonic = libc::CLOCK_MONOTONIC,
+
+    /**
+     * The clock which includes the time spent in suspend, `CLOCK_BOOTTIME`
+     */
+    BootTime = libc::CLOCK_BOOTTIME,
+}
+
 /**
  * The time at which the frame at `position` was presented
  */
@@ -563,3 +824,65 @@ pub struct FrameTimestamp {
      */
     pub timestamp: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use num_traits::FromPrimitive;
+    use std::fmt::Debug;
+
+    use super::*;
+
+    const ERRORS: [Error; 17] = [
+        Error::Disconnected,
+        Error::IllegalArgument,
+        Error::Internal,
+        Error::InvalidState,
+        Error::InvalidHandle,
+        Error::Unimplemented,
+        Error::Unavailable,
+        Error::NoFreeHandles,
+        Error::NoMemory,
+        Error::Null,
+        Error::Timeout,
+        Error::WouldBlock,
+        Error::InvalidFormat,
+        Error::OutOfRange,
+        Error::NoService,
+        Error::InvalidRate,
+        Error::Closed,
+    ];
+
+    #[test]
+    fn error_code_round_trip() {
+        for error in ERRORS {
+            assert_eq!(Error::from_code(error.code()), error);
+            assert_eq!(wrap_status(error.code()), Err(error));
+        }
+    }
+
+    #[test]
+    fn unknown_error_code() {
+        let code = ffi::oboe_Result_ErrorDisconnected - 1;
+        assert_eq!(wrap_status(code), Err(Error::Unknown(code)));
+        assert_eq!(Error::Unknown(code).code(), code);
+        assert_eq!(wrap_status(ffi::oboe_Result_OK), Ok(()));
+    }
+
+    /// The variants are found by their discriminants, so the new ones cannot be missed
+    fn assert_lists_all<T: FromPrimitive + PartialEq + Debug>(all: &[T]) {
+        let variants: Vec<T> = (0..=u16::MAX as i32).filter_map(T::from_i32).collect();
+        assert_eq!(all.len(), variants.len());
+        for variant in &variants {
+            assert!(all.contains(variant), "{:?} is not listed", variant);
+        }
+    }
+
+    #[test]
+    fn all_lists_every_variant() {
+        assert_lists_all(PerformanceMode::all());
+        assert_lists_all(SampleRateConversionQuality::all());
+        assert_lists_all(Usage::all());
+        assert_lists_all(ContentType::all());
+        assert_lists_all(InputPreset::all());
+    }
+}
@@ -0,0 +1,116 @@
+/**
+ * The dithering converter of float samples to 16-bit integer samples
+ *
+ * It uses triangular probability density function (TPDF) dither with first-order
+ * error feedback noise shaping. The error state is kept between calls,
+ * so a single converter should be used for the whole stream.
+ *
+ * The samples are interleaved, each channel has its own error state.
+ */
+pub struct Dither {
+    errors: Vec<f32>,
+    channel: usize,
+    random: u32,
+}
+
+impl Dither {
+    const DEFAULT_SEED: u32 = 0x9E37_79B9;
+
+    /**
+     * Create converter for the specified number of interleaved channels
+     */
+    pub fn new(channels: usize) -> Self {
+        Self::with_seed(channels, Self::DEFAULT_SEED)
+    }
+
+    /**
+     * Create converter with the specified seed of noise generator
+     */
+    pub fn with_seed(channels: usize, seed: u32) -> Self {
+        Self {
+            errors: vec![0.0; channels.max(1)],
+            channel: 0,
+            // The xorshift state must not be zero
+            random: seed.max(1),
+        }
+    }
+
+    /**
+     * Reset the error state
+     */
+    pub fn reset(&mut self) {
+        for error in &mut self.errors {
+            *error = 0.0;
+        }
+        self.channel = 0;
+    }
+
+    /**
+     * Convert the float samples to the integer samples
+     *
+     * Converts the `min(input.len(), output.len())` samples.
+     */
+    pub fn process(&mut self, input: &[f32], output: &mut [i16]) {
+        // The same scale as `IsSample::from_f32` uses
+        const SCALE: f32 = -(i16::MIN as f32);
+
+        for (sample, result) in input.iter().zip(output.iter_mut()) {
+            let error = &mut self.errors[self.channel];
+            let shaped = sample * SCALE - *error;
+
+            let noise = uniform(&mut self.random) - uniform(&mut self.random);
+            let quantized = (shaped + noise).round();
+
+            // The clipping is not a part of the error, otherwise it is fed back
+            *error = quantized - shaped;
+            *result = quantized.clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+
+            self.channel += 1;
+            if self.channel == self.errors.len() {
+                self.channel = 0;
+            }
+        }
+    }
+}
+
+/// Uniform random value in range `0.0..1.0` using xorshift32
+fn uniform(state: &mut u32) -> f32 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    *state = x;
+    (x >> 8) as f32 / (1u32 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mean(samples: &[i16]) -> f64 {
+        samples.iter().map(|&sample| sample as f64).sum::<f64>() / samples.len() as f64
+    }
+
+    #[test]
+    fn keeps_dc_offset() {
+        let mut dither = Dither::new(1);
+        let input = vec![0.25; 48000];
+        let mut output = vec![0; input.len()];
+        dither.process(&input, &mut output);
+
+        let expected = 0.25 * -(i16::MIN as f64);
+        assert!((mean(&output) - expected).abs() < 0.5);
+    }
+
+    #[test]
+    fn clipping_is_not_fed_back() {
+        let mut dither = Dither::new(1);
+        let mut output = vec![0; 1000];
+        dither.process(&[1.5; 1000], &mut output);
+        assert!(output.iter().all(|&sample| sample >= i16::MAX - 2));
+
+        dither.process(&[0.0; 1000], &mut output);
+        assert!(output.iter().all(|&sample| sample.abs() <= 2));
+        assert!(mean(&output).abs() < 0.5);
+    }
+}
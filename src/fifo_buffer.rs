@@ -0,0 +1,301 @@
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/**
+ * The storage shared between the writer and the reader
+ */
+struct FifoShared<T> {
+    buffer: Box<[UnsafeCell<T>]>,
+    read_counter: AtomicU64,
+    write_counter: AtomicU64,
+}
+
+unsafe impl<T: Send> Sync for FifoShared<T> {}
+
+impl<T: Copy> FifoShared<T> {
+    fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn full_frames(&self) -> usize {
+        self.write_counter
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.read_counter.load(Ordering::Acquire)) as usize
+    }
+
+    fn empty_frames(&self) -> usize {
+        self.capacity() - self.full_frames()
+    }
+
+    /// Must be called from the only writer
+    fn write(&self, frames: &[T]) -> usize {
+        let write_counter = self.write_counter.load(Ordering::Relaxed);
+        let count = frames.len().min(self.empty_frames());
+        let start = (write_counter % self.capacity() as u64) as usize;
+
+        for (index, frame) in frames[..count].iter().enumerate() {
+            let cell = &self.buffer[(start + index) % self.capacity()];
+            unsafe { *cell.get() = *frame };
+        }
+
+        self.write_counter
+            .store(write_counter.wrapping_add(count as u64), Ordering::Release);
+        count
+    }
+
+    /// Must be called from the only reader
+    fn read(&self, frames: &mut [T]) -> usize {
+        let read_counter = self.read_counter.load(Ordering::Relaxed);
+        let count = frames.len().min(self.full_frames());
+        let start = (read_counter % self.capacity() as u64) as usize;
+
+        for (index, frame) in frames[..count].iter_mut().enumerate() {
+            let cell = &self.buffer[(start + index) % self.capacity()];
+            *frame = unsafe { *cell.get() };
+        }
+
+        self.read_counter
+            .store(read_counter.wrapping_add(count as u64), Ordering::Release);
+        count
+    }
+}
+
+/**
+ * The first-in first-out buffer of frames
+ *
+ * It follows the semantics of the `FifoBuffer` which Oboe uses internally:
+ * the capacity is fixed in frames, the writing never overwrites the unread frames
+ * and the reading never waits, so both return the number of frames actually transferred.
+ *
+ * To bridge the producer and the consumer running in different threads, split it into
+ * [`FifoWriter`] and [`FifoReader`]. Neither side blocks or allocates, so both can be used
+ * in the data callback.
+ */
+pub struct FifoBuffer<T> {
+    shared: Arc<FifoShared<T>>,
+}
+
+impl<T: Copy + Default> FifoBuffer<T> {
+    /**
+     * Create the buffer which can hold `capacity` frames
+     *
+     * The capacity must not be zero.
+     */
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "The capacity of FIFO must not be zero");
+
+        Self {
+            shared: Arc::new(FifoShared {
+                buffer: (0..capacity)
+                    .map(|_| UnsafeCell::new(T::default()))
+                    .collect(),
+                read_counter: AtomicU64::new(0),
+                write_counter: AtomicU64::new(0),
+            }),
+        }
+    }
+}
+
+impl<T: Copy> FifoBuffer<T> {
+    /**
+     * Get the maximum number of frames which the buffer can hold
+     */
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /**
+     * Get the number of frames which can be read
+     */
+    pub fn available(&self) -> usize {
+        self.shared.full_frames()
+    }
+
+    /**
+     * Get the number of frames which can be written
+     */
+    pub fn available_to_write(&self) -> usize {
+        self.shared.empty_frames()
+    }
+
+    /**
+     * Write as many frames as fit, returns the number of frames written
+     */
+    pub fn write(&mut self, frames: &[T]) -> usize {
+        self.shared.write(frames)
+    }
+
+    /**
+     * Read as many frames as available, returns the number of frames read
+     */
+    pub fn read(&mut self, frames: &mut [T]) -> usize {
+        self.shared.read(frames)
+    }
+
+    /**
+     * Split the buffer into the writing and the reading sides
+     */
+    pub fn split(self) -> (FifoWriter<T>, FifoReader<T>) {
+        (
+            FifoWriter {
+                shared: self.shared.clone(),
+            },
+            FifoReader {
+                shared: self.shared,
+            },
+        )
+    }
+}
+
+impl<T: Copy + Default> FifoBuffer<T> {
+    /**
+     * Read the available frames and fill the rest with default (silence)
+     *
+     * Returns the number of frames actually read, like `readNow` of Oboe.
+     */
+    pub fn read_now(&mut self, frames: &mut [T]) -> usize {
+        read_now(&self.shared, frames)
+    }
+}
+
+/**
+ * The writing side of [`FifoBuffer`]
+ */
+pub struct FifoWriter<T> {
+    shared: Arc<FifoShared<T>>,
+}
+
+impl<T: Copy> FifoWriter<T> {
+    /**
+     * Get the maximum number of frames which the buffer can hold
+     */
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /**
+     * Get the number of frames which can be written
+     */
+    pub fn available(&self) -> usize {
+        self.shared.empty_frames()
+    }
+
+    /**
+     * Write as many frames as fit, returns the number of frames written
+     */
+    pub fn write(&mut self, frames: &[T]) -> usize {
+        self.shared.write(frames)
+    }
+}
+
+/**
+ * The reading side of [`FifoBuffer`]
+ */
+pub struct FifoReader<T> {
+    shared: Arc<FifoShared<T>>,
+}
+
+impl<T: Copy> FifoReader<T> {
+    /**
+     * Get the maximum number of frames which the buffer can hold
+     */
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /**
+     * Get the number of frames which can be read
+     */
+    pub fn available(&self) -> usize {
+        self.shared.full_frames()
+    }
+
+    /**
+     * Read as many frames as available, returns the number of frames read
+     */
+    pub fn read(&mut self, frames: &mut [T]) -> usize {
+        self.shared.read(frames)
+    }
+}
+
+impl<T: Copy + Default> FifoReader<T> {
+    /**
+     * Read the available frames and fill the rest with default (silence)
+     *
+     * Returns the number of frames actually read, like `readNow` of Oboe.
+     */
+    pub fn read_now(&mut self, frames: &mut [T]) -> usize {
+        read_now(&self.shared, frames)
+    }
+}
+
+fn read_now<T: Copy + Default>(shared: &FifoShared<T>, frames: &mut [T]) -> usize {
+    let count = shared.read(frames);
+    for frame in &mut frames[count..] {
+        *frame = T::default();
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_around() {
+        let mut fifo = FifoBuffer::new(4);
+        let mut output = [0; 3];
+
+        for round in 0..5 {
+            let input = [round * 3 + 1, round * 3 + 2, round * 3 + 3];
+            assert_eq!(fifo.write(&input), 3);
+            assert_eq!(fifo.available(), 3);
+            assert_eq!(fifo.available_to_write(), 1);
+
+            assert_eq!(fifo.read(&mut output), 3);
+            assert_eq!(output, input);
+            assert_eq!(fifo.available(), 0);
+        }
+    }
+
+    #[test]
+    fn does_not_overwrite_unread_frames() {
+        let mut fifo = FifoBuffer::new(4);
+        assert_eq!(fifo.write(&[1, 2, 3]), 3);
+        assert_eq!(fifo.write(&[4, 5, 6]), 1);
+
+        let mut output = [0; 2];
+        assert_eq!(fifo.read(&mut output), 2);
+        assert_eq!(output, [1, 2]);
+
+        // The write position wraps around the end of storage
+        assert_eq!(fifo.write(&[5, 6, 7]), 2);
+
+        let mut output = [0; 6];
+        assert_eq!(fifo.read_now(&mut output), 4);
+        assert_eq!(output, [3, 4, 5, 6, 0, 0]);
+    }
+
+    #[test]
+    fn single_frame() {
+        let (mut writer, mut reader) = FifoBuffer::new(1).split();
+        let mut output = [(0.0, 0.0)];
+
+        for index in 0..3 {
+            let frame = (index as f32, -(index as f32));
+            assert_eq!(writer.write(&[frame]), 1);
+            assert_eq!(writer.available(), 0);
+            assert_eq!(writer.write(&[frame]), 0);
+
+            assert_eq!(reader.available(), 1);
+            assert_eq!(reader.read(&mut output), 1);
+            assert_eq!(output, [frame]);
+            assert_eq!(reader.read(&mut output), 0);
+        }
+    }
+}
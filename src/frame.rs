@@ -0,0 +1,365 @@
+use num_traits::NumCast;
+use std::slice;
+
+use super::{IsFormat, RawFrame, RawSample};
+
+/**
+ * The trait for sample types which can be processed generically
+ *
+ * The samples are converted to and from normalized floats in range `-1.0..=1.0`.
+ *
+ * It is implemented for `i16`, `i32` and `f32`.
+ */
+pub trait IsSample: IsFormat + RawSample + Copy + Default + Send + 'static {
+    /**
+     * The minimum value of sample
+     */
+    const MIN_VALUE: Self;
+
+    /**
+     * The maximum value of sample
+     */
+    const MAX_VALUE: Self;
+
+    /**
+     * The value of silence
+     */
+    const SILENCE: Self;
+
+    /**
+     * Convert sample to normalized float
+     */
+    fn to_f32(self) -> f32;
+
+    /**
+     * Convert normalized float to sample
+     *
+     * The out of range values are clipped.
+     */
+    fn from_f32(value: f32) -> Self;
+
+    /**
+     * Get the mean of two samples, i.e. their sum attenuated by 6 dB
+     *
+     * The integer samples are summed with higher precision, so it never overflows.
+     */
+    fn mean(self, other: Self) -> Self;
+}
+
+impl RawSample for i16 {}
+impl RawSample for i32 {}
+impl RawSample for f32 {}
+
+impl IsSample for i16 {
+    const MIN_VALUE: Self = i16::MIN;
+    const MAX_VALUE: Self = i16::MAX;
+    const SILENCE: Self = 0;
+
+    fn to_f32(self) -> f32 {
+        self as f32 / -(i16::MIN as f32)
+    }
+
+    fn from_f32(value: f32) -> Self {
+        (value * -(i16::MIN as f32)) as i16
+    }
+
+    fn mean(self, other: Self) -> Self {
+        ((self as i32 + other as i32) >> 1) as i16
+    }
+}
+
+impl IsSample for i32 {
+    const MIN_VALUE: Self = i32::MIN;
+    const MAX_VALUE: Self = i32::MAX;
+    const SILENCE: Self = 0;
+
+    fn to_f32(self) -> f32 {
+        (self as f64 / -(i32::MIN as f64)) as f32
+    }
+
+    fn from_f32(value: f32) -> Self {
+        (value as f64 * -(i32::MIN as f64)) as i32
+    }
+
+    fn mean(self, other: Self) -> Self {
+        ((self as i64 + other as i64) >> 1) as i32
+    }
+}
+
+impl IsSample for f32 {
+    const MIN_VALUE: Self = -1.0;
+    const MAX_VALUE: Self = 1.0;
+    const SILENCE: Self = 0.0;
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value.clamp(-1.0, 1.0)
+    }
+
+    fn mean(self, other: Self) -> Self {
+        (self + other) * 0.5
+    }
+}
+
+/**
+ * Clamp the value to the range of sample type
+ *
+ * The value is in units of the sample type, e.g. `40000.0` gives `i16::MAX` for `i16`.
+ * It is useful when the intermediate results is accumulated with higher precision.
+ */
+pub fn clamp_sample<T: IsSample + NumCast>(value: f64) -> T {
+    let min = T::MIN_VALUE.to_f64().unwrap();
+    let max = T::MAX_VALUE.to_f64().unwrap();
+    NumCast::from(value.clamp(min, max)).unwrap_or(T::SILENCE)
+}
+
+/**
+ * The trait for frames of samples
+ *
+ * It is implemented for the frame types of mono and stereo streams,
+ * i.e. `T` and `(T, T)` where `T` is a sample type.
+ */
+pub trait IsFrame: RawFrame + Copy + Default + Send + 'static {
+    /**
+     * The type of samples
+     */
+    type Sample: IsSample;
+
+    /**
+     * The number of channels in frame
+     */
+    const CHANNELS: usize;
+
+    /**
+     * Get the sample of specified channel
+     *
+     * Panics when the channel index is out of range.
+     */
+    fn channel(&self, index: usize) -> Self::Sample;
+
+    /**
+     * Get the mutable reference to the sample of specified channel
+     *
+     * Panics when the channel index is out of range.
+     */
+    fn channel_mut(&mut self, index: usize) -> &mut Self::Sample;
+
+    /**
+     * Set the sample of specified channel
+     *
+     * Panics when the channel index is out of range.
+     */
+    fn set_channel(&mut self, index: usize, sample: Self::Sample) {
+        *self.channel_mut(index) = sample;
+    }
+}
+
+impl<T: IsSample> RawFrame for T {}
+impl<T: IsSample> RawFrame for (T, T) {}
+
+impl<T: IsSample> IsFrame for T {
+    type Sample = T;
+
+    const CHANNELS: usize = 1;
+
+    fn channel(&self, index: usize) -> Self::Sample {
+        assert!(index < Self::CHANNELS, "Channel index out of range");
+        *self
+    }
+
+    fn channel_mut(&mut self, index: usize) -> &mut Self::Sample {
+        assert!(index < Self::CHANNELS, "Channel index out of range");
+        self
+    }
+}
+
+impl<T: IsSample> IsFrame for (T, T) {
+    type Sample = T;
+
+    const CHANNELS: usize = 2;
+
+    fn channel(&self, index: usize) -> Self::Sample {
+        match index {
+            0 => self.0,
+            1 => self.1,
+            _ => panic!("Channel index out of range"),
+        }
+    }
+
+    fn channel_mut(&mut self, index: usize) -> &mut Self::Sample {
+        match index {
+            0 => &mut self.0,
+            1 => &mut self.1,
+            _ => panic!("Channel index out of range"),
+        }
+    }
+}
+
+/**
+ * The extension trait for interleaved buffers of frames
+ *
+ * It provides per-channel views without manual stride arithmetic:
+ *
+ * ```ignore
+ * fn on_audio_ready(
+ *     &mut self,
+ *     _stream: &mut dyn AudioOutputStreamSafe,
+ *     frames: &mut [(f32, f32)],
+ * ) -> DataCallbackResult {
+ *     for sample in frames.channel_mut(1) {
+ *         *sample = 0.0;
+ *     }
+ *     DataCallbackResult::Continue
+ * }
+ * ```
+ */
+pub trait FrameBuffer {
+    /**
+     * The type of frames
+     */
+    type Frame: IsFrame;
+
+    /**
+     * Get the number of channels
+     */
+    fn channels(&self) -> usize {
+        <Self::Frame as IsFrame>::CHANNELS
+    }
+
+    /**
+     * Get the sample at specified frame and channel
+     *
+     * Returns `None` when any of indexes is out of range.
+     */
+    fn sample(&self, frame: usize, channel: usize) -> Option<<Self::Frame as IsFrame>::Sample>;
+
+    /**
+     * Get the mutable reference to the sample at specified frame and channel
+     *
+     * Returns `None` when any of indexes is out of range.
+     */
+    fn sample_mut(
+        &mut self,
+        frame: usize,
+        channel: usize,
+    ) -> Option<&mut <Self::Frame as IsFrame>::Sample>;
+
+    /**
+     * Iterate over the samples of specified channel
+     *
+     * Panics when the channel index is out of range.
+     */
+    fn channel(&self, channel: usize) -> Channel<'_, Self::Frame>;
+
+    /**
+     * Iterate over the mutable samples of specified channel
+     *
+     * Panics when the channel index is out of range.
+     */
+    fn channel_mut(&mut self, channel: usize) -> ChannelMut<'_, Self::Frame>;
+}
+
+impl<F: IsFrame> FrameBuffer for [F] {
+    type Frame = F;
+
+    fn sample(&self, frame: usize, channel: usize) -> Option<F::Sample> {
+        if channel < F::CHANNELS {
+            self.get(frame).map(|frame| frame.channel(channel))
+        } else {
+            None
+        }
+    }
+
+    fn sample_mut(&mut self, frame: usize, channel: usize) -> Option<&mut F::Sample> {
+        if channel < F::CHANNELS {
+            self.get_mut(frame).map(|frame| frame.channel_mut(channel))
+        } else {
+            None
+        }
+    }
+
+    fn channel(&self, channel: usize) -> Channel<'_, F> {
+        assert!(channel < F::CHANNELS, "Channel index out of range");
+        Channel {
+            frames: self.iter(),
+            channel,
+        }
+    }
+
+    fn channel_mut(&mut self, channel: usize) -> ChannelMut<'_, F> {
+        assert!(channel < F::CHANNELS, "Channel index out of range");
+        ChannelMut {
+            frames: self.iter_mut(),
+            channel,
+        }
+    }
+}
+
+/**
+ * The iterator over the samples of channel
+ *
+ * See [`FrameBuffer::channel`].
+ */
+pub struct Channel<'a, F> {
+    frames: slice::Iter<'a, F>,
+    channel: usize,
+}
+
+impl<'a, F: IsFrame> Iterator for Channel<'a, F> {
+    type Item = F::Sample;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next().map(|frame| frame.channel(self.channel))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
+
+impl<'a, F: IsFrame> DoubleEndedIterator for Channel<'a, F> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.frames
+            .next_back()
+            .map(|frame| frame.channel(self.channel))
+    }
+}
+
+impl<'a, F: IsFrame> ExactSizeIterator for Channel<'a, F> {}
+
+/**
+ * The iterator over the mutable samples of channel
+ *
+ * See [`FrameBuffer::channel_mut`].
+ */
+pub struct ChannelMut<'a, F> {
+    frames: slice::IterMut<'a, F>,
+    channel: usize,
+}
+
+impl<'a, F: IsFrame> Iterator for ChannelMut<'a, F> {
+    type Item = &'a mut F::Sample;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let channel = self.channel;
+        self.frames.next().map(|frame| frame.channel_mut(channel))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
+
+impl<'a, F: IsFrame> DoubleEndedIterator for ChannelMut<'a, F> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let channel = self.channel;
+        self.frames
+            .next_back()
+            .map(|frame| frame.channel_mut(channel))
+    }
+}
+
+impl<'a, F: IsFrame> ExactSizeIterator for ChannelMut<'a, F> {}
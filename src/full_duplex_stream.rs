@@ -0,0 +1,507 @@
+use std::marker::PhantomData;
+
+use super::{
+    AudioInputCallback, AudioInputStreamSync, AudioOutputCallback, AudioOutputStreamSafe,
+    AudioStream, AudioStreamAsync, AudioStreamBase, AudioStreamBuilder, AudioStreamBuilderAsync,
+    AudioStreamSafe, AudioStreamSync, DataCallbackResult, Input, IsFormat, IsFrameType, Output,
+    PerformanceMode, RawAudioStreamBase, Result, SampleRateConversionQuality, SessionId,
+    SharingMode, Status, Timeout, Unspecified,
+};
+
+/**
+ * Factory for the matched pair of input and output stream builders
+ *
+ * The parameters which must agree for full-duplex processing, i.e. the format and
+ * the sample rate, are set once and applied to both builders, so the halves cannot
+ * be configured with mismatched values by mistake.
+ *
+ * ```ignore
+ * let (input, output) = DuplexBuilder::new()
+ *     .set_f32()
+ *     .set_sample_rate(48000)
+ *     .set_performance_mode(PerformanceMode::LowLatency)
+ *     .build();
+ * let input = input.set_mono().open_stream()?;
+ * let output = output
+ *     .set_stereo()
+ *     .set_frames_per_callback(192)
+ *     .set_callback(FullDuplexStream::new(input, processor, 192))
+ *     .open_stream()?;
+ * ```
+ *
+ * The channel counts may differ, so they are configured on each builder separately.
+ */
+pub struct DuplexBuilder<T> {
+    sample_rate: i32,
+    sample_rate_conversion_quality: SampleRateConversionQuality,
+    performance_mode: PerformanceMode,
+    sharing_mode: SharingMode,
+    _phantom: PhantomData<T>,
+}
+
+impl Default for DuplexBuilder<Unspecified> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DuplexBuilder<Unspecified> {
+    /**
+     * Create the builder with Oboe defaults
+     */
+    pub fn new() -> Self {
+        let builder = AudioStreamBuilder::default();
+        Self {
+            sample_rate: builder.get_sample_rate(),
+            sample_rate_conversion_quality: builder.get_sample_rate_conversion_quality(),
+            performance_mode: builder.get_performance_mode(),
+            sharing_mode: builder.get_sharing_mode(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> DuplexBuilder<T> {
+    /**
+     * Request a sample data format for both streams
+     */
+    pub fn set_format<X: IsFormat>(self) -> DuplexBuilder<X> {
+        DuplexBuilder {
+            sample_rate: self.sample_rate,
+            sample_rate_conversion_quality: self.sample_rate_conversion_quality,
+            performance_mode: self.performance_mode,
+            sharing_mode: self.sharing_mode,
+            _phantom: PhantomData,
+        }
+    }
+
+    /**
+     * Request 16-bit integer format for both streams
+     */
+    pub fn set_i16(self) -> DuplexBuilder<i16> {
+        self.set_format::<i16>()
+    }
+
+    /**
+     * Request float format for both streams
+     */
+    pub fn set_f32(self) -> DuplexBuilder<f32> {
+        self.set_format::<f32>()
+    }
+
+    /**
+     * Request a specific sample rate in Hz for both streams
+     *
+     * When the rate is unspecified each stream may get a different device rate,
+     * so it is recommended to set it explicitly.
+     */
+    pub fn set_sample_rate(mut self, sample_rate: i32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /**
+     * Specify the quality of the sample rate converter for both streams
+     *
+     * When the device does not support the requested rate, the conversion in Oboe
+     * is the only way to keep the rates matched.
+     */
+    pub fn set_sample_rate_conversion_quality(
+        mut self,
+        quality: SampleRateConversionQuality,
+    ) -> Self {
+        self.sample_rate_conversion_quality = quality;
+        self
+    }
+
+    /**
+     * Request a performance level for both streams
+     */
+    pub fn set_performance_mode(mut self, performance_mode: PerformanceMode) -> Self {
+        self.performance_mode = performance_mode;
+        self
+    }
+
+    /**
+     * Request a mode for sharing the devices
+     */
+    pub fn set_sharing_mode(mut self, sharing_mode: SharingMode) -> Self {
+        self.sharing_mode = sharing_mode;
+        self
+    }
+}
+
+impl<T: IsFormat> DuplexBuilder<T> {
+    /**
+     * Create the input and output builders with the shared parameters
+     */
+    #[allow(clippy::type_complexity)]
+    pub fn build(
+        self,
+    ) -> (
+        AudioStreamBuilder<Input, Unspecified, T>,
+        AudioStreamBuilder<Output, Unspecified, T>,
+    ) {
+        (
+            self.apply(AudioStreamBuilder::default().set_input()),
+            self.apply(AudioStreamBuilder::default().set_output()),
+        )
+    }
+
+    fn apply<D>(
+        &self,
+        builder: AudioStreamBuilder<D, Unspecified, Unspecified>,
+    ) -> AudioStreamBuilder<D, Unspecified, T> {
+        builder
+            .set_format::<T>()
+            .set_sample_rate(self.sample_rate)
+            .set_sample_rate_conversion_quality(self.sample_rate_conversion_quality)
+            .set_performance_mode(self.performance_mode)
+            .set_sharing_mode(self.sharing_mode)
+    }
+}
+
+/**
+ * This trait defines a callback interface for full-duplex processing
+ *
+ * See [`FullDuplexStream`].
+ */
+pub trait AudioDuplexCallback {
+    /**
+     * The frame type of input stream
+     */
+    type InputFrameType: IsFrameType;
+
+    /**
+     * The frame type of output stream
+     */
+    type OutputFrameType: IsFrameType;
+
+    /**
+     * Both input and output buffers are ready for processing.
+     *
+     * The input always has the same number of frames as the output.
+     *
+     * The same real-time restrictions as for
+     * [`AudioOutputCallback::on_audio_ready`] apply.
+     */
+    fn on_both_streams_ready(
+        &mut self,
+        input: &[<Self::InputFrameType as IsFrameType>::Type],
+        output: &mut [<Self::OutputFrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult;
+}
+
+/**
+ * The full-duplex stream helper
+ *
+ * It is an output callback which reads the input stream in a non-blocking way and passes
+ * both input and output buffers to the [`AudioDuplexCallback`].
+ *
+ * The input and output callbacks may have different numbers of frames, so the input is
+ * accumulated in the reconciliation buffer and exactly the number of frames requested by
+ * the output is passed to the callback.
+ *
+ * Priming strategy:
+ *
+ * 1. Start the input stream before starting the output stream.
+ * 2. On the first output callback the stale input, which was captured before output started,
+ *    is discarded to keep the latency low.
+ * 3. Until the buffer contains enough input frames, the output is filled by silence instead
+ *    of blocking, and the duplex callback is not called.
+ *
+ * When the buffer overflows, e.g. because the input runs faster, the oldest frames are
+ * discarded.
+ */
+pub struct FullDuplexStream<I: IsFrameType, T, S = AudioStreamSync<Input, I>> {
+    input: S,
+    callback: T,
+    buffer: Vec<I::Type>,
+    available: usize,
+    primed: bool,
+}
+
+impl<I, T, S> FullDuplexStream<I, T, S>
+where
+    I: IsFrameType,
+    I::Type: Copy + Default,
+    S: AudioInputStreamSync<FrameType = I>,
+{
+    /**
+     * Create full-duplex stream from an opened input stream and duplex callback
+     *
+     * The `frames` is the largest number of frames which the output callback requests,
+     * e.g. the frames per callback set on the output builder. The reconciliation buffer
+     * is allocated here with the capacity of twice the larger of `frames` and the input
+     * buffer capacity, so the callback never allocates. When the output requests more frames,
+     * the output is filled by silence.
+     */
+    pub fn new(input: S, callback: T, frames: usize) -> Self {
+        let frames = frames.max(input.get_buffer_capacity_in_frames().max(0) as usize);
+        let capacity = (frames * 2).max(1);
+        Self {
+            input,
+            callback,
+            buffer: vec![Default::default(); capacity],
+            available: 0,
+            primed: false,
+        }
+    }
+
+    /**
+     * Get the number of input frames which can be passed to the next callback
+     *
+     * It includes the frames in the reconciliation buffer and the frames available
+     * in the input stream.
+     */
+    pub fn get_input_frames_available(&mut self) -> usize {
+        self.available + self.input.get_available_frames().unwrap_or(0).max(0) as usize
+    }
+
+    /**
+     * Get the input stream
+     */
+    pub fn get_input(&mut self) -> &mut S {
+        &mut self.input
+    }
+
+    fn discard_input(&mut self) -> Status {
+        while self.input.read(&mut self.buffer, Timeout::None)? > 0 {}
+        self.available = 0;
+        Ok(())
+    }
+
+    fn fill_input(&mut self) -> Status {
+        if self.available == self.buffer.len() {
+            // Overflow: drop the oldest half of the buffer
+            let half = self.available / 2;
+            self.buffer.copy_within(half..self.available, 0);
+            self.available -= half;
+        }
+
+        let read = self
+            .input
+            .read(&mut self.buffer[self.available..], Timeout::None)?;
+        self.available += read.max(0) as usize;
+        Ok(())
+    }
+}
+
+impl<I, T, S> AudioOutputCallback for FullDuplexStream<I, T, S>
+where
+    I: IsFrameType,
+    I::Type: Copy + Default,
+    S: AudioInputStreamSync<FrameType = I>,
+    T: AudioDuplexCallback<InputFrameType = I>,
+    <T::OutputFrameType as IsFrameType>::Type: Default,
+{
+    type FrameType = T::OutputFrameType;
+
+    fn on_audio_ready(
+        &mut self,
+        _audio_stream: &mut dyn AudioOutputStreamSafe,
+        audio_data: &mut [<Self::FrameType as IsFrameType>::Type],
+    ) -> DataCallbackResult {
+        let frames = audio_data.len();
+
+        let status = if self.primed {
+            self.fill_input()
+        } else {
+            self.primed = true;
+            self.discard_input()
+        };
+
+        if status.is_err() {
+            // The input stream was disconnected or closed
+            self.input.request_stop().ok();
+            return DataCallbackResult::Stop;
+        }
+
+        if self.available < frames || frames > self.buffer.len() / 2 {
+            for frame in audio_data.iter_mut() {
+                *frame = Default::default();
+            }
+            return DataCallbackResult::Continue;
+        }
+
+        let result = self
+            .callback
+            .on_both_streams_ready(&self.buffer[..frames], audio_data);
+
+        self.buffer.copy_within(frames..self.available, 0);
+        self.available -= frames;
+
+        result
+    }
+}
+
+/**
+ * The input and output streams which share the audio session
+ *
+ * It is intended for monitoring while recording, e.g. in karaoke apps: the input captures
+ * the voice (consider [`InputPreset::VoicePerformance`](crate::InputPreset::VoicePerformance))
+ * and the output plays the backing track mixed with the monitored voice. The streams share
+ * the session ID, so the Android `AudioEffect`s attached to the session apply to both.
+ *
+ * Startup ordering: the input is started before the output, so the output never monitors
+ * the input before it captures, and the output is stopped before the input, so the last
+ * captured buffer is not played repeatedly. Monitoring through the speaker still causes
+ * feedback, so prefer headphones.
+ *
+ * The voice is recorded later than the backing track was played by the round-trip latency,
+ * see [`MonitorStreams::latency_compensation_frames`] to align them.
+ */
+pub struct MonitorStreams<I, O> {
+    input: AudioStreamAsync<Input, I>,
+    output: AudioStreamAsync<Output, O>,
+}
+
+impl<I, O> MonitorStreams<I, O>
+where
+    I: AudioInputCallback + Send,
+    O: AudioOutputCallback + Send,
+{
+    /**
+     * Open the input stream with the newly allocated session, then the output stream with
+     * the same session
+     *
+     * The session IDs are supported by AAudio only since Android 9 (API 28), on older versions
+     * the streams are opened without the shared session.
+     */
+    pub fn open(
+        mut input: AudioStreamBuilderAsync<Input, I>,
+        mut output: AudioStreamBuilderAsync<Output, O>,
+    ) -> Result<Self> {
+        input._raw_base_mut().mSessionId = SessionId::Allocate as i32;
+        let input = input.open_stream()?;
+
+        output._raw_base_mut().mSessionId = input
+            .get_allocated_session_id()
+            .unwrap_or(SessionId::None as i32);
+        let output = output.open_stream()?;
+
+        Ok(Self { input, output })
+    }
+}
+
+impl<I, O> MonitorStreams<I, O> {
+    /**
+     * Get the shared session ID, when it was allocated
+     */
+    pub fn session_id(&self) -> Option<i32> {
+        self.input.get_allocated_session_id()
+    }
+
+    /**
+     * Start the input stream, then the output stream
+     *
+     * When the output fails to start, the input is stopped.
+     */
+    pub fn start(&mut self) -> Status {
+        self.input.start()?;
+        if let Err(error) = self.output.start() {
+            self.input.stop().ok();
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /**
+     * Stop the output stream, then the input stream
+     */
+    pub fn stop(&mut self) -> Status {
+        let output = self.output.stop();
+        let input = self.input.stop();
+        output.and(input)
+    }
+
+    /**
+     * Estimate the round-trip latency from the output to the input in milliseconds
+     */
+    pub fn round_trip_latency_millis(&mut self) -> Result<f64> {
+        Ok(self.output.calculate_latency_millis()? + self.input.calculate_latency_millis()?)
+    }
+
+    /**
+     * Get the number of frames by which the recording lags behind the playback
+     *
+     * Skip this number of recorded frames to align the voice with the backing track.
+     */
+    pub fn latency_compensation_frames(&mut self) -> Result<i64> {
+        let millis = self.round_trip_latency_millis()?;
+        Ok((millis * self.input.get_sample_rate() as f64 / 1000.0).round() as i64)
+    }
+
+    /**
+     * Get the input stream
+     */
+    pub fn input(&mut self) -> &mut AudioStreamAsync<Input, I> {
+        &mut self.input
+    }
+
+    /**
+     * Get the output stream
+     */
+    pub fn output(&mut self) -> &mut AudioStreamAsync<Output, O> {
+        &mut self.output
+    }
+}
+
+#[cfg(all(test, feature = "test-driver"))]
+mod tests {
+    use super::*;
+    use crate::{test_stream::TestStream, CallbackDriver, Mono};
+
+    struct Passthrough;
+
+    impl AudioDuplexCallback for Passthrough {
+        type InputFrameType = (f32, Mono);
+        type OutputFrameType = (f32, Mono);
+
+        fn on_both_streams_ready(
+            &mut self,
+            input: &[f32],
+            output: &mut [f32],
+        ) -> DataCallbackResult {
+            output.copy_from_slice(input);
+            DataCallbackResult::Continue
+        }
+    }
+
+    #[test]
+    fn output_burst_larger_than_input_capacity() {
+        const INPUT_BURST: usize = 48;
+        const OUTPUT_BURST: usize = 192;
+
+        let mut input = TestStream::new();
+        input.capacity = INPUT_BURST as i32;
+
+        let duplex = FullDuplexStream::new(input, Passthrough, OUTPUT_BURST);
+        let mut driver = CallbackDriver::output(duplex, 48000);
+
+        // The stale input is discarded on the first callback
+        driver
+            .callback()
+            .get_input()
+            .frames
+            .extend([1.0; OUTPUT_BURST]);
+        assert_eq!(driver.render(OUTPUT_BURST), vec![0.0; OUTPUT_BURST]);
+
+        let mut next = 0.0;
+        for _ in 0..4 {
+            let mut expected = Vec::new();
+            for _ in 0..OUTPUT_BURST / INPUT_BURST {
+                let chunk: Vec<f32> = (0..INPUT_BURST)
+                    .map(|_| {
+                        next += 1.0;
+                        next
+                    })
+                    .collect();
+                driver.callback().get_input().frames.extend(&chunk);
+                expected.extend(chunk);
+            }
+            assert_eq!(driver.render(OUTPUT_BURST), expected);
+            assert_eq!(driver.last_result(), Some(DataCallbackResult::Continue));
+        }
+    }
+}
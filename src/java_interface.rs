@@ -1,10 +1,20 @@
+mod audio_attributes;
 mod audio_features;
+mod audio_focus;
+mod communication_device;
 mod definitions;
 mod devices_info;
+mod input_effects;
+mod spatializer;
 mod stream_defaults;
 mod utils;
 
+pub use self::audio_attributes::*;
 pub use self::audio_features::*;
+pub use self::audio_focus::*;
+pub use self::communication_device::*;
 pub use self::definitions::*;
 pub use self::devices_info::*;
+pub use self::input_effects::*;
+pub use self::spatializer::*;
 pub use self::stream_defaults::*;
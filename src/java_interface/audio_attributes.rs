@@ -0,0 +1,49 @@
+use num_traits::FromPrimitive;
+
+use crate::{AudioStreamBuilder, ContentType, PerformanceMode, Usage};
+
+use super::{
+    utils::{call_method_no_args_ret_int, JNIEnv, JObject},
+    AudioAttributes,
+};
+
+impl<D, C, T> AudioStreamBuilder<D, C, T> {
+    /**
+     * Apply the Java `android.media.AudioAttributes` to the builder
+     *
+     * The usage and the content type are copied as is, since their values are the same in Java
+     * and in AAudio. The `FLAG_LOW_LATENCY` flag requests the
+     * [`PerformanceMode::LowLatency`]. Unknown values leave the corresponding setting unchanged.
+     *
+     * The `env` must be attached to the current thread and the `attributes` must be a valid
+     * local or global reference in it. The reference is only used during the call,
+     * so it is not deleted and remains owned by the caller.
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+    pub fn set_from_audio_attributes(
+        mut self,
+        env: &JNIEnv,
+        attributes: JObject,
+    ) -> Result<Self, String> {
+        let read = || -> jni::errors::Result<_> {
+            Ok((
+                call_method_no_args_ret_int(env, attributes, "getUsage")?,
+                call_method_no_args_ret_int(env, attributes, "getContentType")?,
+                call_method_no_args_ret_int(env, attributes, "getFlags")?,
+            ))
+        };
+        let (usage, content_type, flags) = read().map_err(|error| error.to_string())?;
+
+        if let Some(usage) = Usage::from_i32(usage) {
+            self = self.set_usage(usage);
+        }
+        if let Some(content_type) = ContentType::from_i32(content_type) {
+            self = self.set_content_type(content_type);
+        }
+        if flags & AudioAttributes::FLAG_LOW_LATENCY != 0 {
+            self = self.set_performance_mode(PerformanceMode::LowLatency);
+        }
+
+        Ok(self)
+    }
+}
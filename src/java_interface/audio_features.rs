@@ -1,9 +1,11 @@
+use crate::PerformanceMode;
+
 use super::{
     utils::{
         get_context, get_package_manager, has_system_feature, with_attached, JNIEnv, JObject,
         JResult,
     },
-    PackageManager,
+    AudioDeviceDirection, AudioDeviceInfo, PackageManager,
 };
 
 /**
@@ -46,6 +48,99 @@ impl AudioFeature {
     }
 }
 
+/**
+ * The audio performance class of the device
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DeviceAudioTier {
+    /**
+     * No low latency audio guarantees
+     */
+    Standard,
+
+    /**
+     * The device declares [`AudioFeature::LowLatency`]
+     */
+    LowLatency,
+
+    /**
+     * The device declares [`AudioFeature::Pro`] which implies low latency
+     */
+    ProAudio,
+}
+
+/**
+ * Classify the audio performance of the device using Android Java API
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn device_audio_tier() -> Result<DeviceAudioTier, String> {
+    let context = get_context();
+
+    with_attached(context, try_get_device_audio_tier).map_err(|error| error.to_string())
+}
+
+/**
+ * Get the performance modes which are expected to work with the device (best effort)
+ *
+ * The [`PerformanceMode::None`] and [`PerformanceMode::PowerSaving`] are always supported.
+ * The [`PerformanceMode::LowLatency`] is reported only when the device declares
+ * [`AudioFeature::LowLatency`] or [`AudioFeature::Pro`], and the device with `device_id`
+ * is not connected over Bluetooth, which has no low latency path. Use zero `device_id`
+ * to check the default device.
+ *
+ * Android gives no guarantees for particular device, so the stream may still get
+ * the higher latency. When the support cannot be determined all modes are returned.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn supported_performance_modes(device_id: i32) -> Vec<PerformanceMode> {
+    let all_modes = vec![
+        PerformanceMode::None,
+        PerformanceMode::PowerSaving,
+        PerformanceMode::LowLatency,
+    ];
+
+    let tier = match device_audio_tier() {
+        Ok(tier) => tier,
+        Err(_) => return all_modes,
+    };
+
+    let wireless = if device_id != 0 {
+        match AudioDeviceInfo::request(AudioDeviceDirection::InputOutput) {
+            Ok(devices) => devices
+                .iter()
+                .any(|device| device.id == device_id && device.device_type.is_wireless()),
+            Err(_) => return all_modes,
+        }
+    } else {
+        false
+    };
+
+    if tier >= DeviceAudioTier::LowLatency && !wireless {
+        all_modes
+    } else {
+        vec![PerformanceMode::None, PerformanceMode::PowerSaving]
+    }
+}
+
+fn try_get_device_audio_tier<'a>(env: &JNIEnv<'a>, activity: JObject) -> JResult<DeviceAudioTier> {
+    let package_manager = get_package_manager(env, activity)?;
+
+    Ok(
+        if has_system_feature(env, package_manager, PackageManager::FEATURE_AUDIO_PRO)? {
+            DeviceAudioTier::ProAudio
+        } else if has_system_feature(
+            env,
+            package_manager,
+            PackageManager::FEATURE_AUDIO_LOW_LATENCY,
+        )? {
+            DeviceAudioTier::LowLatency
+        } else {
+            DeviceAudioTier::Standard
+        },
+    )
+}
+
 fn try_check_system_feature<'a>(
     env: &JNIEnv<'a>,
     activity: JObject,
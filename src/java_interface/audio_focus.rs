@@ -0,0 +1,347 @@
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Mutex, PoisonError},
+};
+
+use jni::{
+    objects::{GlobalRef, JClass},
+    sys::{jint, jlong},
+};
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+
+use crate::Usage;
+
+use super::{
+    utils::{get_context, get_system_service, with_attached, JNIEnv, JObject, JResult},
+    AudioManager, Context,
+};
+
+/**
+ * The kind of audio focus to request
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum FocusGain {
+    /**
+     * Focus for an unknown duration, e.g. music playback
+     */
+    Gain = AudioManager::AUDIOFOCUS_GAIN,
+
+    /**
+     * Temporary focus, e.g. navigation directions
+     */
+    Transient = AudioManager::AUDIOFOCUS_GAIN_TRANSIENT,
+
+    /**
+     * Temporary focus while other apps may keep playing ducked
+     */
+    TransientMayDuck = AudioManager::AUDIOFOCUS_GAIN_TRANSIENT_MAY_DUCK,
+
+    /**
+     * Temporary focus while nobody else should play, e.g. voice memo recording
+     */
+    TransientExclusive = AudioManager::AUDIOFOCUS_GAIN_TRANSIENT_EXCLUSIVE,
+}
+
+/**
+ * The audio focus change notified by the system
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive)]
+#[repr(i32)]
+pub enum FocusChange {
+    /**
+     * The focus was gained or regained, playback can be resumed at normal volume
+     */
+    Gain = AudioManager::AUDIOFOCUS_GAIN,
+
+    /**
+     * The focus was lost for an unknown duration, playback should be stopped
+     */
+    Loss = AudioManager::AUDIOFOCUS_LOSS,
+
+    /**
+     * The focus was lost temporarily, e.g. by a phone call, playback should be paused
+     */
+    LossTransient = AudioManager::AUDIOFOCUS_LOSS_TRANSIENT,
+
+    /**
+     * The focus was lost temporarily, e.g. by a notification, playback may continue with lowered volume
+     */
+    Duck = AudioManager::AUDIOFOCUS_LOSS_TRANSIENT_CAN_DUCK,
+}
+
+type FocusHandler = Mutex<Option<Box<dyn FnMut(FocusChange) + Send>>>;
+
+/**
+ * The audio focus helper
+ *
+ * On Android 8.0 and newer the focus is requested using `AudioFocusRequest` with the audio
+ * attributes of the stream usage, on older versions it is requested for the legacy stream
+ * type which corresponds to the usage.
+ *
+ * The focus changes are passed to the handler by the `rs.oboe.AudioFocusListener` Java class,
+ * because native code cannot implement `AudioManager.OnAudioFocusChangeListener`. The class is
+ * shipped in the `java` directory of this crate, and the app must include it into its build
+ * when the handler is set, otherwise the request fails. The handler is called on the main
+ * thread of the app.
+ *
+ * The focus is abandoned when the helper is dropped.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub struct AudioFocus {
+    usage: Usage,
+    stream_type: i32,
+    handler: Box<FocusHandler>,
+    listener: Option<GlobalRef>,
+    request: Option<GlobalRef>,
+}
+
+impl AudioFocus {
+    const LISTENER_CLASS: &'static str = "rs.oboe.AudioFocusListener";
+
+    /**
+     * Create audio focus helper for streams with specified usage
+     */
+    pub fn new(usage: Usage) -> Self {
+        Self {
+            usage,
+            stream_type: usage_to_stream_type(usage),
+            handler: Box::new(Mutex::new(None)),
+            listener: None,
+            request: None,
+        }
+    }
+
+    /**
+     * Set the handler of audio focus changes
+     *
+     * It must be set before the focus is requested, e.g. to pause on
+     * [`FocusChange::LossTransient`] and to lower volume on [`FocusChange::Duck`].
+     */
+    pub fn set_handler<F>(&mut self, handler: F)
+    where
+        F: FnMut(FocusChange) + Send + 'static,
+    {
+        *self.handler.lock().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(handler));
+    }
+
+    /**
+     * Request audio focus using Android Java API
+     *
+     * Returns `true` when the focus was granted.
+     */
+    pub fn request(&mut self, gain: FocusGain) -> Result<bool, String> {
+        let context = get_context();
+
+        with_attached(context, |env, context| {
+            let sdk_version = env
+                .get_static_field("android/os/Build$VERSION", "SDK_INT", "I")?
+                .i()?;
+
+            let has_handler = self
+                .handler
+                .lock()
+                .map_or(true, |handler| handler.is_some());
+            if self.listener.is_none() && has_handler {
+                let handle = &*self.handler as *const FocusHandler as jlong;
+                let listener = try_new_listener(env, context, handle).inspect_err(|_| {
+                    // The class is missing when the app does not include it
+                    let _ = env.exception_clear();
+                })?;
+                self.listener = Some(env.new_global_ref(listener)?);
+            }
+            let listener = self
+                .listener
+                .as_ref()
+                .map(GlobalRef::as_obj)
+                .unwrap_or_else(JObject::null);
+
+            let audio_manager = get_system_service(env, context, Context::AUDIO_SERVICE)?;
+
+            if sdk_version >= 26 {
+                let request = try_new_focus_request(env, self.usage, gain as i32, listener)?;
+                let result = env
+                    .call_method(
+                        audio_manager,
+                        "requestAudioFocus",
+                        "(Landroid/media/AudioFocusRequest;)I",
+                        &[request.into()],
+                    )?
+                    .i()?;
+                self.request = Some(env.new_global_ref(request)?);
+                Ok(result)
+            } else {
+                env.call_method(
+                    audio_manager,
+                    "requestAudioFocus",
+                    "(Landroid/media/AudioManager$OnAudioFocusChangeListener;II)I",
+                    &[
+                        listener.into(),
+                        self.stream_type.into(),
+                        (gain as i32).into(),
+                    ],
+                )?
+                .i()
+            }
+        })
+        .map(|result| result == AudioManager::AUDIOFOCUS_REQUEST_GRANTED)
+        .map_err(|error| error.to_string())
+    }
+
+    /**
+     * Abandon audio focus using Android Java API
+     */
+    pub fn abandon(&mut self) -> Result<(), String> {
+        let context = get_context();
+
+        with_attached(context, |env, context| {
+            let audio_manager = get_system_service(env, context, Context::AUDIO_SERVICE)?;
+
+            match self.request.take() {
+                Some(request) => env.call_method(
+                    audio_manager,
+                    "abandonAudioFocusRequest",
+                    "(Landroid/media/AudioFocusRequest;)I",
+                    &[request.as_obj().into()],
+                )?,
+                None => {
+                    let listener = self
+                        .listener
+                        .as_ref()
+                        .map(GlobalRef::as_obj)
+                        .unwrap_or_else(JObject::null);
+                    env.call_method(
+                        audio_manager,
+                        "abandonAudioFocus",
+                        "(Landroid/media/AudioManager$OnAudioFocusChangeListener;)I",
+                        &[listener.into()],
+                    )?
+                }
+            }
+            .i()
+        })
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+    }
+}
+
+impl Drop for AudioFocus {
+    fn drop(&mut self) {
+        let _ = self.abandon();
+
+        if let Some(listener) = self.listener.take() {
+            let context = get_context();
+
+            // The handler must outlive the notifications which are still running
+            let _ = with_attached(context, |env, _context| {
+                env.call_method(listener.as_obj(), "release", "()V", &[])
+                    .map(|_| ())
+            });
+        }
+    }
+}
+
+#[no_mangle]
+extern "system" fn Java_rs_oboe_AudioFocusListener_nativeOnAudioFocusChange(
+    _env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+    focus_change: jint,
+) {
+    let handler = unsafe { &*(handle as *const FocusHandler) };
+
+    // The panic must not unwind into Java
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        let mut handler = handler.lock().unwrap_or_else(PoisonError::into_inner);
+        if let (Some(handler), Some(focus_change)) =
+            (handler.as_mut(), FocusChange::from_i32(focus_change))
+        {
+            handler(focus_change);
+        }
+    }));
+}
+
+fn usage_to_stream_type(usage: Usage) -> i32 {
+    use Usage::*;
+    match usage {
+        VoiceCommunication => AudioManager::STREAM_VOICE_CALL,
+        VoiceCommunicationSignalling => AudioManager::STREAM_DTMF,
+        Alarm => AudioManager::STREAM_ALARM,
+        NotificationRingtone => AudioManager::STREAM_RING,
+        Notification | NotificationEvent => AudioManager::STREAM_NOTIFICATION,
+        AssistanceSonification => AudioManager::STREAM_SYSTEM,
+        Media | Game | AssistanceAccessibility | AssistanceNavigationGuidance | Assistant => {
+            AudioManager::STREAM_MUSIC
+        }
+    }
+}
+
+fn try_new_listener<'a>(
+    env: &JNIEnv<'a>,
+    context: JObject<'a>,
+    handle: jlong,
+) -> JResult<JObject<'a>> {
+    // The class of app is not visible to the system class loader of native thread
+    let class_loader = env
+        .call_method(context, "getClassLoader", "()Ljava/lang/ClassLoader;", &[])?
+        .l()?;
+    let class = env
+        .call_method(
+            class_loader,
+            "loadClass",
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+            &[JObject::from(env.new_string(AudioFocus::LISTENER_CLASS)?).into()],
+        )?
+        .l()?;
+
+    env.new_object(JClass::from(class), "(J)V", &[handle.into()])
+}
+
+fn try_new_focus_request<'a>(
+    env: &JNIEnv<'a>,
+    usage: Usage,
+    gain: i32,
+    listener: JObject<'a>,
+) -> JResult<JObject<'a>> {
+    let builder = env.new_object("android/media/AudioAttributes$Builder", "()V", &[])?;
+    let builder = env
+        .call_method(
+            builder,
+            "setUsage",
+            "(I)Landroid/media/AudioAttributes$Builder;",
+            &[(usage as i32).into()],
+        )?
+        .l()?;
+    let attributes = env
+        .call_method(builder, "build", "()Landroid/media/AudioAttributes;", &[])?
+        .l()?;
+
+    let builder = env.new_object(
+        "android/media/AudioFocusRequest$Builder",
+        "(I)V",
+        &[gain.into()],
+    )?;
+    let mut builder = env
+        .call_method(
+            builder,
+            "setAudioAttributes",
+            "(Landroid/media/AudioAttributes;)Landroid/media/AudioFocusRequest$Builder;",
+            &[attributes.into()],
+        )?
+        .l()?;
+    if !listener.is_null() {
+        builder = env
+            .call_method(
+                builder,
+                "setOnAudioFocusChangeListener",
+                "(Landroid/media/AudioManager$OnAudioFocusChangeListener;)Landroid/media/AudioFocusRequest$Builder;",
+                &[listener.into()],
+            )?
+            .l()?;
+    }
+    env.call_method(builder, "build", "()Landroid/media/AudioFocusRequest;", &[])?
+        .l()
+}
@@ -0,0 +1,173 @@
+use num_traits::FromPrimitive;
+
+use super::{
+    utils::{
+        call_method_no_args_ret_int, get_context, get_devices, get_system_service, with_attached,
+        JList, JNIEnv, JObject, JResult, JValue,
+    },
+    AudioDeviceType, AudioManager, Context,
+};
+
+/**
+ * Route the communication audio, e.g. VoIP call, to the device with specified id
+ *
+ * On Android 12 (API 31) and newer `AudioManager.setCommunicationDevice` is used, so the device
+ * must be one of the available communication devices.
+ *
+ * On older versions the routing is emulated using the legacy switches:
+ *
+ * - built-in earpiece, wired or USB headset turn the speakerphone and Bluetooth SCO off
+ * - built-in speaker turns the speakerphone on
+ * - Bluetooth SCO device starts Bluetooth SCO
+ *
+ * The legacy switches take effect only in `MODE_IN_COMMUNICATION` or `MODE_IN_CALL` audio mode,
+ * which the application should set, and Bluetooth SCO requires the `BLUETOOTH` permission.
+ *
+ * Returns `false` when the device cannot be used for communication.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn set_communication_device(device_id: i32) -> Result<bool, String> {
+    let context = get_context();
+
+    with_attached(context, |env, context| {
+        let sdk_version = env
+            .get_static_field("android/os/Build$VERSION", "SDK_INT", "I")?
+            .i()?;
+
+        let audio_manager = get_system_service(env, context, Context::AUDIO_SERVICE)?;
+
+        if sdk_version >= 31 {
+            try_set_communication_device(env, audio_manager, device_id)
+        } else if sdk_version >= 23 {
+            try_route_legacy(env, audio_manager, device_id)
+        } else {
+            Ok(false)
+        }
+    })
+    .map_err(|error| error.to_string())
+}
+
+/**
+ * Cancel the routing of communication audio set by [`set_communication_device`]
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn clear_communication_device() -> Result<(), String> {
+    let context = get_context();
+
+    with_attached(context, |env, context| {
+        let sdk_version = env
+            .get_static_field("android/os/Build$VERSION", "SDK_INT", "I")?
+            .i()?;
+
+        let audio_manager = get_system_service(env, context, Context::AUDIO_SERVICE)?;
+
+        if sdk_version >= 31 {
+            env.call_method(audio_manager, "clearCommunicationDevice", "()V", &[])?;
+        } else {
+            set_speakerphone(env, audio_manager, false)?;
+            set_bluetooth_sco(env, audio_manager, false)?;
+        }
+        Ok(())
+    })
+    .map_err(|error| error.to_string())
+}
+
+fn try_set_communication_device<'a>(
+    env: &JNIEnv<'a>,
+    audio_manager: JObject<'a>,
+    device_id: i32,
+) -> JResult<bool> {
+    let devices = env
+        .call_method(
+            audio_manager,
+            "getAvailableCommunicationDevices",
+            "()Ljava/util/List;",
+            &[],
+        )?
+        .l()?;
+
+    for device in JList::from_env(env, devices)?.iter()? {
+        if call_method_no_args_ret_int(env, device, "getId")? == device_id {
+            return env
+                .call_method(
+                    audio_manager,
+                    "setCommunicationDevice",
+                    "(Landroid/media/AudioDeviceInfo;)Z",
+                    &[device.into()],
+                )?
+                .z();
+        }
+    }
+
+    Ok(false)
+}
+
+fn try_route_legacy<'a>(
+    env: &JNIEnv<'a>,
+    audio_manager: JObject<'a>,
+    device_id: i32,
+) -> JResult<bool> {
+    let devices = env.auto_local(get_devices(
+        env,
+        audio_manager,
+        AudioManager::GET_DEVICES_OUTPUTS,
+    )?);
+
+    let raw_devices = devices.as_obj().into_inner();
+
+    let mut device_type = None;
+    for index in 0..env.get_array_length(raw_devices)? {
+        let device = env.get_object_array_element(raw_devices, index)?;
+        if call_method_no_args_ret_int(env, device, "getId")? == device_id {
+            device_type =
+                AudioDeviceType::from_i32(call_method_no_args_ret_int(env, device, "getType")?);
+            break;
+        }
+    }
+
+    use AudioDeviceType::*;
+    match device_type {
+        Some(BuiltinEarpiece) | Some(WiredHeadset) | Some(WiredHeadphones) | Some(UsbHeadset) => {
+            set_bluetooth_sco(env, audio_manager, false)?;
+            set_speakerphone(env, audio_manager, false)?;
+            Ok(true)
+        }
+        Some(BuiltinSpeaker) => {
+            set_bluetooth_sco(env, audio_manager, false)?;
+            set_speakerphone(env, audio_manager, true)?;
+            Ok(true)
+        }
+        Some(BluetoothSCO) => {
+            set_speakerphone(env, audio_manager, false)?;
+            set_bluetooth_sco(env, audio_manager, true)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn set_speakerphone<'a>(env: &JNIEnv<'a>, audio_manager: JObject<'a>, on: bool) -> JResult<()> {
+    env.call_method(
+        audio_manager,
+        "setSpeakerphoneOn",
+        "(Z)V",
+        &[JValue::Bool(on as u8)],
+    )?;
+    Ok(())
+}
+
+fn set_bluetooth_sco<'a>(env: &JNIEnv<'a>, audio_manager: JObject<'a>, on: bool) -> JResult<()> {
+    let method = if on {
+        "startBluetoothSco"
+    } else {
+        "stopBluetoothSco"
+    };
+    env.call_method(audio_manager, method, "()V", &[])?;
+    env.call_method(
+        audio_manager,
+        "setBluetoothScoOn",
+        "(Z)V",
+        &[JValue::Bool(on as u8)],
+    )?;
+    Ok(())
+}
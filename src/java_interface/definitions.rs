@@ -18,6 +18,28 @@ impl PackageManager {
     pub const FEATURE_MIDI: &'static str = "android.software.midi";
 }
 
+pub(crate) struct Spatializer;
+
+impl Spatializer {
+    pub const SPATIALIZER_IMMERSIVE_LEVEL_OTHER: i32 = -1;
+    pub const SPATIALIZER_IMMERSIVE_LEVEL_NONE: i32 = 0;
+    pub const SPATIALIZER_IMMERSIVE_LEVEL_MULTICHANNEL: i32 = 1;
+}
+
+pub(crate) struct AudioAttributes;
+
+impl AudioAttributes {
+    pub const USAGE_MEDIA: i32 = 1;
+
+    pub const FLAG_LOW_LATENCY: i32 = 1 << 8;
+}
+
+pub(crate) struct AudioEffect;
+
+impl AudioEffect {
+    pub const SUCCESS: i32 = 0;
+}
+
 pub(crate) struct AudioManager;
 
 impl AudioManager {
@@ -29,6 +51,24 @@ impl AudioManager {
     pub const GET_DEVICES_INPUTS: i32 = 1 << 0;
     pub const GET_DEVICES_OUTPUTS: i32 = 1 << 1;
     pub const GET_DEVICES_ALL: i32 = Self::GET_DEVICES_INPUTS | Self::GET_DEVICES_OUTPUTS;
+
+    pub const STREAM_VOICE_CALL: i32 = 0;
+    pub const STREAM_SYSTEM: i32 = 1;
+    pub const STREAM_RING: i32 = 2;
+    pub const STREAM_MUSIC: i32 = 3;
+    pub const STREAM_ALARM: i32 = 4;
+    pub const STREAM_NOTIFICATION: i32 = 5;
+    pub const STREAM_DTMF: i32 = 8;
+
+    pub const AUDIOFOCUS_GAIN: i32 = 1;
+    pub const AUDIOFOCUS_GAIN_TRANSIENT: i32 = 2;
+    pub const AUDIOFOCUS_GAIN_TRANSIENT_MAY_DUCK: i32 = 3;
+    pub const AUDIOFOCUS_GAIN_TRANSIENT_EXCLUSIVE: i32 = 4;
+    pub const AUDIOFOCUS_LOSS: i32 = -Self::AUDIOFOCUS_GAIN;
+    pub const AUDIOFOCUS_LOSS_TRANSIENT: i32 = -Self::AUDIOFOCUS_GAIN_TRANSIENT;
+    pub const AUDIOFOCUS_LOSS_TRANSIENT_CAN_DUCK: i32 = -Self::AUDIOFOCUS_GAIN_TRANSIENT_MAY_DUCK;
+
+    pub const AUDIOFOCUS_REQUEST_GRANTED: i32 = 1;
 }
 
 /**
@@ -119,6 +159,83 @@ pub enum AudioDeviceType {
     Unsupported = -1,
 }
 
+impl AudioDeviceType {
+    /**
+     * Get the category of device type for presenting in UI
+     */
+    pub fn category(&self) -> DeviceCategory {
+        use self::AudioDeviceType::*;
+        match self {
+            BuiltinEarpiece | BuiltinMic | BuiltinSpeaker | BuiltinSpeakerSafe => {
+                DeviceCategory::Speaker
+            }
+            WiredHeadphones | WiredHeadset | LineAnalog | AuxLine => DeviceCategory::Headphones,
+            BluetoothA2DP | BluetoothSCO | BleBroadcast | BleHeadset | BleSpeaker | HearingAid => {
+                DeviceCategory::Bluetooth
+            }
+            UsbAccessory | UsbDevice | UsbHeadset => DeviceCategory::Usb,
+            Hdmi | HdmiArc | HdmiEarc => DeviceCategory::Hdmi,
+            Bus | Dock | Fm | FmTuner | Ip | LineDigital | RemoteSubmix | Telephony | TvTuner
+            | Unknown | Unsupported => DeviceCategory::Other,
+        }
+    }
+
+    /**
+     * Returns true if the device is connected over the air
+     */
+    pub fn is_wireless(&self) -> bool {
+        self.category() == DeviceCategory::Bluetooth
+    }
+
+    /**
+     * Returns true if the device is external and connected by a cable
+     */
+    pub fn is_wired(&self) -> bool {
+        use self::AudioDeviceType::*;
+        match self.category() {
+            DeviceCategory::Headphones | DeviceCategory::Usb | DeviceCategory::Hdmi => true,
+            _ => matches!(self, Dock | LineDigital),
+        }
+    }
+}
+
+/**
+ * The category of audio device type
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeviceCategory {
+    /**
+     * The built-in speaker, earpiece or microphone
+     */
+    Speaker,
+
+    /**
+     * The device connected to the headphone jack, including analog line connections
+     */
+    Headphones,
+
+    /**
+     * The Bluetooth device, including BLE audio and hearing aids
+     */
+    Bluetooth,
+
+    /**
+     * The USB device, headset or accessory
+     */
+    Usb,
+
+    /**
+     * The HDMI output, including audio return channels
+     */
+    Hdmi,
+
+    /**
+     * The other device, e.g. dock, telephony, tuner or digital line
+     */
+    Other,
+}
+
 /**
  * The direction of audio device
  */
@@ -152,10 +269,74 @@ impl AudioDeviceDirection {
     }
 }
 
+/**
+ * The compressed audio encoding which the device may play through
+ *
+ * See [`is_compressed_format_supported`](crate::is_compressed_format_supported).
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum CompressedEncoding {
+    /**
+     * Dolby Digital
+     */
+    Ac3 = AudioFormat::ENCODING_AC3,
+
+    /**
+     * Dolby Digital Plus
+     */
+    EAc3 = AudioFormat::ENCODING_E_AC3,
+
+    /**
+     * Dolby Digital Plus with Dolby Atmos (joint object coding)
+     */
+    EAc3Joc = AudioFormat::ENCODING_E_AC3_JOC,
+
+    /**
+     * Dolby AC-4
+     */
+    Ac4 = AudioFormat::ENCODING_AC4,
+
+    /**
+     * Dolby TrueHD
+     */
+    DolbyTrueHd = AudioFormat::ENCODING_DOLBY_TRUEHD,
+
+    /**
+     * Dolby Metadata-enhanced Audio Transmission
+     */
+    DolbyMat = AudioFormat::ENCODING_DOLBY_MAT,
+
+    /**
+     * DTS
+     */
+    Dts = AudioFormat::ENCODING_DTS,
+
+    /**
+     * DTS-HD
+     */
+    DtsHd = AudioFormat::ENCODING_DTS_HD,
+
+    /**
+     * Any compressed audio wrapped into IEC 61937 frames for passthrough
+     */
+    Iec61937 = AudioFormat::ENCODING_IEC61937,
+}
+
 impl AudioFormat {
     pub(crate) const ENCODING_PCM_16BIT: i32 = 2;
     //pub(crate) const ENCODING_PCM_8BIT: i32 = 3;
     pub(crate) const ENCODING_PCM_FLOAT: i32 = 4;
+    pub(crate) const ENCODING_AC3: i32 = 5;
+    pub(crate) const ENCODING_E_AC3: i32 = 6;
+    pub(crate) const ENCODING_DTS: i32 = 7;
+    pub(crate) const ENCODING_DTS_HD: i32 = 8;
+    pub(crate) const ENCODING_IEC61937: i32 = 13;
+    pub(crate) const ENCODING_DOLBY_TRUEHD: i32 = 14;
+    pub(crate) const ENCODING_AC4: i32 = 17;
+    pub(crate) const ENCODING_E_AC3_JOC: i32 = 18;
+    pub(crate) const ENCODING_DOLBY_MAT: i32 = 19;
 
     pub(crate) fn from_encoding(encoding: i32) -> Option<AudioFormat> {
         match encoding {
@@ -1,15 +1,19 @@
 use num_traits::FromPrimitive;
 
-use crate::AudioFormat;
+use crate::{
+    AudioFormat, AudioStreamBuilder, ChannelCount, DefaultStreamValues, RawAudioStreamBase,
+    SampleRateConversionQuality, Unspecified,
+};
 
 use super::{
     utils::{
         call_method_no_args_ret_bool, call_method_no_args_ret_char_sequence,
         call_method_no_args_ret_int, call_method_no_args_ret_int_array,
         call_method_no_args_ret_string, get_context, get_devices, get_system_service,
-        with_attached, JNIEnv, JObject, JResult,
+        with_attached, JList, JNIEnv, JObject, JResult,
     },
-    AudioDeviceDirection, AudioDeviceInfo, AudioDeviceType, Context,
+    AudioAttributes, AudioDeviceDirection, AudioDeviceInfo, AudioDeviceType, CompressedEncoding,
+    Context,
 };
 
 impl AudioDeviceInfo {
@@ -36,6 +40,379 @@ impl AudioDeviceInfo {
         })
         .map_err(|error| error.to_string())
     }
+
+    /**
+     * Choose the supported sample rate which is the closest to the preferred one
+     *
+     * The empty list of supported rates means that any rate is allowed,
+     * so the preferred rate is returned as is. When two rates are equally close
+     * the higher one is chosen.
+     *
+     * Returns `None` when the preferred rate is unspecified (not positive).
+     */
+    pub fn best_sample_rate(&self, preferred: i32) -> Option<i32> {
+        if preferred <= 0 {
+            None
+        } else if self.sample_rates.is_empty() || self.sample_rates.contains(&preferred) {
+            Some(preferred)
+        } else {
+            self.sample_rates
+                .iter()
+                .copied()
+                .filter(|rate| *rate > 0)
+                .min_by_key(|rate| ((*rate as i64 - preferred as i64).abs(), -rate))
+        }
+    }
+
+    /**
+     * Get the native sample rate of the device
+     *
+     * The default sample rate (see [`DefaultStreamValues`]) is preferred when the device
+     * supports it, otherwise the highest supported rate is chosen.
+     *
+     * Returns `None` when the device lists no rates and the default rate is unknown.
+     */
+    pub fn native_sample_rate(&self) -> Option<i32> {
+        let default = DefaultStreamValues::get_sample_rate();
+        if default > 0 && (self.sample_rates.is_empty() || self.sample_rates.contains(&default)) {
+            Some(default)
+        } else {
+            self.sample_rates
+                .iter()
+                .copied()
+                .filter(|rate| *rate > 0)
+                .max()
+        }
+    }
+
+    /**
+     * Get the native channel count of the device
+     *
+     * Stereo is preferred when listed, then mono. Returns `None` for other layouts,
+     * which cannot be requested.
+     */
+    pub fn native_channel_count(&self) -> Option<ChannelCount> {
+        if self.channel_counts.contains(&2) {
+            Some(ChannelCount::Stereo)
+        } else if self.channel_counts.contains(&1) {
+            Some(ChannelCount::Mono)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Get the native format of the device
+     *
+     * When the device lists several formats the float one is preferred, then the 16-bit one.
+     */
+    pub fn native_format(&self) -> Option<AudioFormat> {
+        if self.formats.contains(&AudioFormat::F32) {
+            Some(AudioFormat::F32)
+        } else if self.formats.contains(&AudioFormat::I16) {
+            Some(AudioFormat::I16)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Get the maximum number of channels supported by the device
+     *
+     * The device may list no channel counts at all, in that case
+     * the conservative stereo (2 channels) is assumed.
+     */
+    pub fn max_channel_count(&self) -> i32 {
+        self.channel_counts
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(DEFAULT_MAX_CHANNEL_COUNT)
+    }
+}
+
+impl<D, C, T> AudioStreamBuilder<D, C, T> {
+    /**
+     * Request the sample rate which is supported by the device
+     *
+     * The preferred rate is requested if the device supports it, otherwise the closest
+     * supported rate is requested to avoid resampling. The sample rate is left unchanged
+     * when no rate can be chosen.
+     *
+     * See [`AudioDeviceInfo::best_sample_rate`].
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+    pub fn set_best_sample_rate(self, device: &AudioDeviceInfo, preferred: i32) -> Self {
+        match device.best_sample_rate(preferred) {
+            Some(sample_rate) => self.set_sample_rate(sample_rate),
+            None => self,
+        }
+    }
+}
+
+impl<D> AudioStreamBuilder<D, Unspecified, Unspecified> {
+    /**
+     * Request exactly the native configuration of the device to avoid conversion
+     *
+     * This is the fast path: the stream is opened on the device with its native
+     * sample rate, channel count and format, and the conversion by Oboe is disabled.
+     * See [`AudioDeviceInfo::native_sample_rate`], [`AudioDeviceInfo::native_channel_count`]
+     * and [`AudioDeviceInfo::native_format`] for the selection priority. The parameter is left
+     * unspecified when the device does not report it.
+     *
+     * The chosen channel count and format are not known at compile time, so open the
+     * synchronous stream and use
+     * [`AudioStreamSync::reinterpret_format`](crate::AudioStreamSync::reinterpret_format),
+     * or query the native format first to choose the builder type.
+     */
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+    pub fn match_device_native(mut self, device: &AudioDeviceInfo) -> Self {
+        if let Some(channel_count) = device.native_channel_count() {
+            self._raw_base_mut().mChannelCount = channel_count as i32;
+        }
+        if let Some(format) = device.native_format() {
+            self._raw_base_mut().mFormat = format as i32;
+        }
+        let builder = self
+            .set_device_id(device.id)
+            .set_channel_conversion_allowed(false)
+            .set_format_conversion_allowed(false)
+            .set_sample_rate_conversion_quality(SampleRateConversionQuality::None);
+        match device.native_sample_rate() {
+            Some(sample_rate) => builder.set_sample_rate(sample_rate),
+            None => builder,
+        }
+    }
+}
+
+/**
+ * The channel count which is assumed when the device does not list any
+ */
+const DEFAULT_MAX_CHANNEL_COUNT: i32 = 2;
+
+/**
+ * Get the maximum number of channels supported by any output device
+ *
+ * It is useful to decide whether the multichannel layouts like 5.1 or 7.1 can be offered.
+ * Returns 2 when no output devices are found.
+ *
+ * See [`AudioDeviceInfo::max_channel_count`].
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn max_output_channels() -> Result<i32, String> {
+    Ok(AudioDeviceInfo::request(AudioDeviceDirection::Output)?
+        .iter()
+        .map(AudioDeviceInfo::max_channel_count)
+        .max()
+        .unwrap_or(DEFAULT_MAX_CHANNEL_COUNT))
+}
+
+/**
+ * Check that the output device can play the compressed encoding, e.g. for HDMI passthrough
+ *
+ * The encodings reported by the device are checked, so it tells whether the sink, e.g.
+ * the AV receiver, accepts the encoded stream, in which case it can be passed through
+ * with [`CompressedEncoding::Iec61937`] instead of decoding to PCM.
+ *
+ * Returns `false` when the device is not found, reports no encodings, or the Android API
+ * is older than 23, where the device encodings are not available.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn is_compressed_format_supported(
+    device_id: i32,
+    encoding: CompressedEncoding,
+) -> Result<bool, String> {
+    let context = get_context();
+
+    with_attached(context, |env, context| {
+        let sdk_version = env
+            .get_static_field("android/os/Build$VERSION", "SDK_INT", "I")?
+            .i()?;
+
+        if sdk_version >= 23 {
+            try_get_device_encodings(env, context, device_id)
+        } else {
+            Ok(Vec::new())
+        }
+    })
+    .map(|encodings| encodings.contains(&(encoding as i32)))
+    .map_err(|error| error.to_string())
+}
+
+/**
+ * The output device types in order of preference of Android audio policy for media
+ */
+const OUTPUT_ROUTING_ORDER: &[AudioDeviceType] = &[
+    AudioDeviceType::BluetoothA2DP,
+    AudioDeviceType::BleHeadset,
+    AudioDeviceType::BleSpeaker,
+    AudioDeviceType::HearingAid,
+    AudioDeviceType::WiredHeadphones,
+    AudioDeviceType::LineAnalog,
+    AudioDeviceType::WiredHeadset,
+    AudioDeviceType::UsbHeadset,
+    AudioDeviceType::UsbAccessory,
+    AudioDeviceType::UsbDevice,
+    AudioDeviceType::Dock,
+    AudioDeviceType::Hdmi,
+    AudioDeviceType::LineDigital,
+    AudioDeviceType::AuxLine,
+    AudioDeviceType::BuiltinSpeaker,
+];
+
+/**
+ * The input device types in order of preference of Android audio policy for microphone
+ */
+const INPUT_ROUTING_ORDER: &[AudioDeviceType] = &[
+    AudioDeviceType::WiredHeadset,
+    AudioDeviceType::UsbHeadset,
+    AudioDeviceType::UsbDevice,
+    AudioDeviceType::BuiltinMic,
+];
+
+/**
+ * Get the id of output device which Android would route media playback to
+ *
+ * On Android API 33 and newer the routing is requested for media usage. On older versions
+ * the connected device is chosen in order of preference of the default audio policy:
+ * Bluetooth, wired headphones, USB, dock, HDMI and finally the built-in speaker.
+ * The vendor policy may differ, so it is an approximation.
+ *
+ * Returns `None` when no default device can be discerned.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn default_output_device_id() -> Result<Option<i32>, String> {
+    let context = get_context();
+
+    let routed = with_attached(context, |env, context| {
+        let sdk_version = env
+            .get_static_field("android/os/Build$VERSION", "SDK_INT", "I")?
+            .i()?;
+
+        if sdk_version >= 33 {
+            try_get_media_device_id(env, context)
+        } else {
+            Ok(None)
+        }
+    })
+    .map_err(|error| error.to_string())?;
+
+    match routed {
+        Some(id) => Ok(Some(id)),
+        None => default_device_id(AudioDeviceDirection::Output, OUTPUT_ROUTING_ORDER),
+    }
+}
+
+/**
+ * Get the id of input device which Android would capture from by default
+ *
+ * Android has no API to query the input routing, so the connected device is chosen in order
+ * of preference of the default audio policy: wired headset, USB and finally the built-in
+ * microphone. Bluetooth headsets are used for input only during calls, so they are skipped.
+ *
+ * Returns `None` when no default device can be discerned.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn default_input_device_id() -> Result<Option<i32>, String> {
+    default_device_id(AudioDeviceDirection::Input, INPUT_ROUTING_ORDER)
+}
+
+/**
+ * Get the output device which media playback is currently routed to
+ *
+ * On Android API 33 and newer the actual routing of media usage is requested. On older
+ * versions the Java routing query needs an `AudioTrack`, so the device is guessed
+ * like in [`default_output_device_id`].
+ *
+ * The open stream knows its routed device exactly: with AAudio
+ * [`AudioStreamBase::get_device_id`](crate::AudioStreamBase::get_device_id) of the stream
+ * returns the id of it, which can be looked up in [`AudioDeviceInfo::request`] results.
+ *
+ * Returns `None` when no device can be discerned.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn current_output_route() -> Result<Option<AudioDeviceInfo>, String> {
+    let device_id = match default_output_device_id()? {
+        Some(device_id) => device_id,
+        None => return Ok(None),
+    };
+
+    Ok(AudioDeviceInfo::request(AudioDeviceDirection::Output)?
+        .into_iter()
+        .find(|device| device.id == device_id))
+}
+
+fn default_device_id(
+    direction: AudioDeviceDirection,
+    order: &[AudioDeviceType],
+) -> Result<Option<i32>, String> {
+    let devices = AudioDeviceInfo::request(direction)?;
+
+    Ok(order.iter().find_map(|device_type| {
+        devices
+            .iter()
+            .find(|device| device.device_type as i32 == *device_type as i32)
+            .map(|device| device.id)
+    }))
+}
+
+fn try_get_media_device_id<'a>(env: &JNIEnv<'a>, context: JObject) -> JResult<Option<i32>> {
+    let audio_manager = get_system_service(env, context, Context::AUDIO_SERVICE)?;
+
+    let builder = env.new_object("android/media/AudioAttributes$Builder", "()V", &[])?;
+    let builder = env
+        .call_method(
+            builder,
+            "setUsage",
+            "(I)Landroid/media/AudioAttributes$Builder;",
+            &[AudioAttributes::USAGE_MEDIA.into()],
+        )?
+        .l()?;
+    let attributes = env
+        .call_method(builder, "build", "()Landroid/media/AudioAttributes;", &[])?
+        .l()?;
+
+    let devices = env
+        .call_method(
+            audio_manager,
+            "getAudioDevicesForAttributes",
+            "(Landroid/media/AudioAttributes;)Ljava/util/List;",
+            &[attributes.into()],
+        )?
+        .l()?;
+
+    match JList::from_env(env, devices)?.get(0)? {
+        Some(device) => Ok(Some(call_method_no_args_ret_int(env, device, "getId")?)),
+        None => Ok(None),
+    }
+}
+
+fn try_get_device_encodings<'a>(
+    env: &JNIEnv<'a>,
+    context: JObject,
+    device_id: i32,
+) -> JResult<Vec<i32>> {
+    let audio_manager = get_system_service(env, context, Context::AUDIO_SERVICE)?;
+
+    let devices = env.auto_local(get_devices(
+        env,
+        audio_manager,
+        AudioDeviceDirection::Output as i32,
+    )?);
+
+    let raw_devices = devices.as_obj().into_inner();
+
+    let length = env.get_array_length(raw_devices)?;
+
+    for index in 0..length {
+        let device = env.get_object_array_element(raw_devices, index)?;
+
+        if call_method_no_args_ret_int(env, device, "getId")? == device_id {
+            return call_method_no_args_ret_int_array(env, device, "getEncodings");
+        }
+    }
+
+    Ok(Vec::new())
 }
 
 fn try_request_devices_info<'a>(
@@ -0,0 +1,192 @@
+use jni::objects::GlobalRef;
+
+use super::{
+    utils::{
+        call_method_no_args_ret_bool, get_context, with_attached, JNIEnv, JObject, JResult, JValue,
+    },
+    AudioEffect,
+};
+
+/**
+ * The platform pre-processing effect of input stream
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputEffectKind {
+    /**
+     * Automatic gain control (AGC)
+     */
+    AutomaticGainControl,
+
+    /**
+     * Noise suppression (NS)
+     */
+    NoiseSuppressor,
+
+    /**
+     * Acoustic echo cancellation (AEC)
+     */
+    AcousticEchoCanceler,
+}
+
+impl InputEffectKind {
+    fn class(self) -> &'static str {
+        match self {
+            InputEffectKind::AutomaticGainControl => "android/media/audiofx/AutomaticGainControl",
+            InputEffectKind::NoiseSuppressor => "android/media/audiofx/NoiseSuppressor",
+            InputEffectKind::AcousticEchoCanceler => "android/media/audiofx/AcousticEchoCanceler",
+        }
+    }
+
+    fn signature(self) -> &'static str {
+        match self {
+            InputEffectKind::AutomaticGainControl => {
+                "(I)Landroid/media/audiofx/AutomaticGainControl;"
+            }
+            InputEffectKind::NoiseSuppressor => "(I)Landroid/media/audiofx/NoiseSuppressor;",
+            InputEffectKind::AcousticEchoCanceler => {
+                "(I)Landroid/media/audiofx/AcousticEchoCanceler;"
+            }
+        }
+    }
+
+    /**
+     * Check that the device implements the effect using Android Java API
+     */
+    pub fn is_available(self) -> Result<bool, String> {
+        let context = get_context();
+
+        with_attached(context, |env, _context| {
+            env.call_static_method(self.class(), "isAvailable", "()Z", &[])?
+                .z()
+        })
+        .map_err(|error| error.to_string())
+    }
+}
+
+/**
+ * Check that the automatic gain control is implemented by the device
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn is_agc_available() -> Result<bool, String> {
+    InputEffectKind::AutomaticGainControl.is_available()
+}
+
+/**
+ * Check that the noise suppression is implemented by the device
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn is_ns_available() -> Result<bool, String> {
+    InputEffectKind::NoiseSuppressor.is_available()
+}
+
+/**
+ * Check that the acoustic echo cancellation is implemented by the device
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub fn is_aec_available() -> Result<bool, String> {
+    InputEffectKind::AcousticEchoCanceler.is_available()
+}
+
+/**
+ * The control of pre-processing effect attached to the input stream
+ *
+ * The effect is attached by audio session ID, so the stream must be opened with
+ * [`SessionId::Allocate`](crate::SessionId::Allocate), then the ID is taken using
+ * [`AudioStreamBase::get_allocated_session_id`](crate::AudioStreamBase::get_allocated_session_id).
+ * The platform may enable effects by default depending on the input preset, e.g. for
+ * [`InputPreset::VoiceCommunication`](crate::InputPreset::VoiceCommunication), so the control
+ * can be used to disable them as well.
+ *
+ * The effect is released when the control is dropped.
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+pub struct InputEffect {
+    kind: InputEffectKind,
+    effect: GlobalRef,
+}
+
+impl InputEffect {
+    /**
+     * Attach the effect to the audio session using Android Java API
+     *
+     * Returns `None` when the effect is not available for the session.
+     */
+    pub fn create(kind: InputEffectKind, session_id: i32) -> Result<Option<Self>, String> {
+        let context = get_context();
+
+        with_attached(context, |env, _context| {
+            let effect = env
+                .call_static_method(
+                    kind.class(),
+                    "create",
+                    kind.signature(),
+                    &[session_id.into()],
+                )?
+                .l()?;
+
+            if effect.is_null() {
+                Ok(None)
+            } else {
+                Ok(Some(Self {
+                    kind,
+                    effect: env.new_global_ref(effect)?,
+                }))
+            }
+        })
+        .map_err(|error| error.to_string())
+    }
+
+    /**
+     * Get the kind of effect
+     */
+    pub fn kind(&self) -> InputEffectKind {
+        self.kind
+    }
+
+    /**
+     * Enable or disable the effect
+     *
+     * Returns `false` when the effect refused the change, e.g. when another application
+     * controls it.
+     */
+    pub fn set_enabled(&self, enabled: bool) -> Result<bool, String> {
+        let context = get_context();
+
+        with_attached(context, |env, _context| {
+            try_set_enabled(env, self.effect.as_obj(), enabled)
+        })
+        .map_err(|error| error.to_string())
+    }
+
+    /**
+     * Check that the effect is enabled
+     */
+    pub fn is_enabled(&self) -> Result<bool, String> {
+        let context = get_context();
+
+        with_attached(context, |env, _context| {
+            call_method_no_args_ret_bool(env, self.effect.as_obj(), "getEnabled")
+        })
+        .map_err(|error| error.to_string())
+    }
+}
+
+impl Drop for InputEffect {
+    fn drop(&mut self) {
+        let context = get_context();
+
+        let _ = with_attached(context, |env, _context| {
+            env.call_method(self.effect.as_obj(), "release", "()V", &[])
+                .map(|_| ())
+        });
+    }
+}
+
+fn try_set_enabled<'a>(env: &JNIEnv<'a>, effect: JObject, enabled: bool) -> JResult<bool> {
+    let status = env
+        .call_method(effect, "setEnabled", "(Z)I", &[JValue::Bool(enabled as u8)])?
+        .i()?;
+
+    Ok(status == AudioEffect::SUCCESS)
+}
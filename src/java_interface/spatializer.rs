@@ -0,0 +1,110 @@
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+
+use super::{
+    utils::{
+        call_method_no_args_ret_bool, call_method_no_args_ret_int, get_context, get_system_service,
+        with_attached, JNIEnv, JObject, JResult,
+    },
+    Context, Spatializer,
+};
+
+/**
+ * The level of immersive audio supported by the spatializer
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive)]
+#[repr(i32)]
+pub enum SpatializerLevel {
+    /**
+     * Non standard immersive audio
+     */
+    Other = Spatializer::SPATIALIZER_IMMERSIVE_LEVEL_OTHER,
+
+    /**
+     * No spatialization
+     */
+    None = Spatializer::SPATIALIZER_IMMERSIVE_LEVEL_NONE,
+
+    /**
+     * Spatialization of multichannel audio
+     */
+    Multichannel = Spatializer::SPATIALIZER_IMMERSIVE_LEVEL_MULTICHANNEL,
+}
+
+/**
+ * The state of the platform audio spatializer
+ */
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "java-interface")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpatializerState {
+    /**
+     * The spatializer can be used with the current output device
+     */
+    pub available: bool,
+
+    /**
+     * The spatial audio is enabled by user in system settings
+     */
+    pub enabled: bool,
+
+    /**
+     * The immersive audio level of the spatializer
+     */
+    pub level: SpatializerLevel,
+}
+
+impl SpatializerState {
+    /**
+     * Request the state of spatializer using Android Java API
+     *
+     * Returns `None` when the spatializer is not supported, i.e. on Android API below 32.
+     *
+     * The state may be changed by user in system settings or by switching the output device
+     * at any time, so consider requesting it again when the audio device changes.
+     */
+    pub fn request() -> Result<Option<Self>, String> {
+        let context = get_context();
+
+        with_attached(context, |env, context| {
+            let sdk_version = env
+                .get_static_field("android/os/Build$VERSION", "SDK_INT", "I")?
+                .i()?;
+
+            if sdk_version < 32 {
+                Ok(None)
+            } else {
+                try_get_spatializer_state(env, context).map(Some)
+            }
+        })
+        .map_err(|error| error.to_string())
+    }
+
+    /**
+     * Check that the platform spatializes the output, so own HRTF processing should be skipped
+     */
+    pub fn is_active(&self) -> bool {
+        self.available && self.enabled && self.level != SpatializerLevel::None
+    }
+}
+
+fn try_get_spatializer_state<'a>(env: &JNIEnv<'a>, context: JObject) -> JResult<SpatializerState> {
+    let audio_manager = get_system_service(env, context, Context::AUDIO_SERVICE)?;
+
+    let spatializer = env
+        .call_method(
+            audio_manager,
+            "getSpatializer",
+            "()Landroid/media/Spatializer;",
+            &[],
+        )?
+        .l()?;
+
+    let level = call_method_no_args_ret_int(env, spatializer, "getImmersiveAudioLevel")?;
+
+    Ok(SpatializerState {
+        available: call_method_no_args_ret_bool(env, spatializer, "isAvailable")?,
+        enabled: call_method_no_args_ret_bool(env, spatializer, "isEnabled")?,
+        level: SpatializerLevel::from_i32(level).unwrap_or(SpatializerLevel::Other),
+    })
+}
@@ -5,9 +5,28 @@ mod audio_stream;
 mod audio_stream_base;
 mod audio_stream_builder;
 mod audio_stream_callback;
+#[cfg(feature = "test-driver")]
+mod callback_driver;
+mod callbacks;
+mod config_warning;
 mod definitions;
+mod dither;
+mod fifo_buffer;
+mod frame;
+mod full_duplex_stream;
+mod open_outcome;
+mod performance_hint;
 mod private;
+mod resampler;
+mod started_stream;
+mod stream_config;
+mod stream_health;
+mod stream_io;
+mod stream_metadata;
+#[cfg(test)]
+mod test_stream;
 mod type_guide;
+mod util;
 mod version;
 
 #[cfg(feature = "java-interface")]
@@ -17,9 +36,26 @@ pub use self::audio_stream::*;
 pub use self::audio_stream_base::*;
 pub use self::audio_stream_builder::*;
 pub use self::audio_stream_callback::*;
+#[cfg(feature = "test-driver")]
+pub use self::callback_driver::*;
+pub use self::callbacks::*;
+pub use self::config_warning::*;
 pub use self::definitions::*;
+pub use self::dither::*;
+pub use self::fifo_buffer::*;
+pub use self::frame::*;
+pub use self::full_duplex_stream::*;
+pub use self::open_outcome::*;
+pub(crate) use self::performance_hint::*;
 pub(crate) use self::private::*;
+pub use self::resampler::*;
+pub use self::started_stream::*;
+pub use self::stream_config::*;
+pub use self::stream_health::*;
+pub use self::stream_io::*;
+pub use self::stream_metadata::*;
 pub use self::type_guide::*;
+pub use self::util::*;
 pub use self::version::*;
 
 #[cfg(feature = "java-interface")]
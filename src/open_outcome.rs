@@ -0,0 +1,198 @@
+use std::fmt::{self, Display};
+
+use super::{
+    AudioApi, AudioFormat, AudioStreamBase, AudioStreamSafe, ChannelCount, PerformanceMode,
+    SharingMode,
+};
+
+/**
+ * The negotiable parameters of an audio stream
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamParameters {
+    /**
+     * The underlying audio API
+     */
+    pub audio_api: AudioApi,
+
+    /**
+     * The sharing mode
+     */
+    pub sharing_mode: SharingMode,
+
+    /**
+     * The performance mode
+     */
+    pub performance_mode: PerformanceMode,
+
+    /**
+     * The number of channels
+     */
+    pub channel_count: ChannelCount,
+
+    /**
+     * The audio sample format
+     */
+    pub format: AudioFormat,
+
+    /**
+     * The sample rate
+     */
+    pub sample_rate: i32,
+
+    /**
+     * The buffer capacity in frames
+     */
+    pub buffer_capacity_in_frames: i32,
+}
+
+impl StreamParameters {
+    pub(crate) fn new<T: AudioStreamBase>(base: &T, audio_api: AudioApi) -> Self {
+        Self {
+            audio_api,
+            sharing_mode: base.get_sharing_mode(),
+            performance_mode: base.get_performance_mode(),
+            channel_count: base.get_channel_count(),
+            format: base.get_format(),
+            sample_rate: base.get_sample_rate(),
+            buffer_capacity_in_frames: base.get_buffer_capacity_in_frames(),
+        }
+    }
+}
+
+/**
+ * The result of stream negotiation
+ *
+ * It contains parameters which was requested by builder and
+ * parameters which was actually granted by system on opening the stream.
+ *
+ * Note that unspecified requested values are usually changed to the actual values.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenOutcome {
+    /**
+     * The parameters requested by builder
+     */
+    pub requested: StreamParameters,
+
+    /**
+     * The parameters granted by system
+     */
+    pub granted: StreamParameters,
+}
+
+impl OpenOutcome {
+    pub(crate) fn new<T: AudioStreamSafe>(requested: StreamParameters, stream: &T) -> Self {
+        Self {
+            requested,
+            granted: StreamParameters::new(stream, stream.get_audio_api()),
+        }
+    }
+
+    /**
+     * Returns true if the system changed at least one of the requested parameters
+     */
+    pub fn is_changed(&self) -> bool {
+        self.requested != self.granted
+    }
+}
+
+/**
+ * The result of requesting the lowest latency
+ *
+ * See [`AudioStreamBuilder::request_lowest_latency`](crate::AudioStreamBuilder::request_lowest_latency).
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LowLatencyOutcome {
+    /**
+     * The stream uses AAudio with exclusive and low latency modes,
+     * see [`AudioStreamSafe::is_exclusive_used`]
+     */
+    pub achieved: bool,
+
+    /**
+     * The granted sharing mode
+     */
+    pub sharing_mode: SharingMode,
+
+    /**
+     * The granted performance mode
+     */
+    pub performance_mode: PerformanceMode,
+
+    /**
+     * The estimated latency in milliseconds, when it can be calculated
+     */
+    pub latency_millis: Option<f64>,
+}
+
+impl LowLatencyOutcome {
+    /**
+     * Check the modes granted to the opened stream
+     *
+     * The latency is calculated only while the stream is running,
+     * so check the started stream to get it.
+     */
+    pub fn new<T: AudioStreamSafe + ?Sized>(stream: &mut T) -> Self {
+        Self {
+            achieved: stream.is_exclusive_used(),
+            sharing_mode: stream.get_sharing_mode(),
+            performance_mode: stream.get_performance_mode(),
+            latency_millis: stream.calculate_latency_millis().ok(),
+        }
+    }
+}
+
+fn outcome_fmt<T: fmt::Debug + PartialEq>(
+    name: &str,
+    requested: &T,
+    granted: &T,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    name.fmt(f)?;
+    ": ".fmt(f)?;
+    if requested != granted {
+        fmt::Debug::fmt(requested, f)?;
+        " -> ".fmt(f)?;
+    }
+    fmt::Debug::fmt(granted, f)?;
+    '\n'.fmt(f)
+}
+
+impl Display for OpenOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let OpenOutcome { requested, granted } = self;
+        outcome_fmt("Audio API", &requested.audio_api, &granted.audio_api, f)?;
+        outcome_fmt(
+            "Sharing mode",
+            &requested.sharing_mode,
+            &granted.sharing_mode,
+            f,
+        )?;
+        outcome_fmt(
+            "Performance mode",
+            &requested.performance_mode,
+            &granted.performance_mode,
+            f,
+        )?;
+        outcome_fmt(
+            "Channel count",
+            &requested.channel_count,
+            &granted.channel_count,
+            f,
+        )?;
+        outcome_fmt("Format", &requested.format, &granted.format, f)?;
+        outcome_fmt(
+            "Sample rate",
+            &requested.sample_rate,
+            &granted.sample_rate,
+            f,
+        )?;
+        outcome_fmt(
+            "Buffer capacity in frames",
+            &requested.buffer_capacity_in_frames,
+            &granted.buffer_capacity_in_frames,
+            f,
+        )
+    }
+}
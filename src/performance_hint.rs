@@ -0,0 +1,201 @@
+use std::{
+    ffi::c_void,
+    os::raw::c_int,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        OnceLock,
+    },
+};
+
+use libc::CLOCK_MONOTONIC;
+
+use super::clock_nanos;
+
+type GetManagerFn = unsafe extern "C" fn() -> *mut c_void;
+type CreateSessionFn = unsafe extern "C" fn(*mut c_void, *const i32, usize, i64) -> *mut c_void;
+type UpdateTargetFn = unsafe extern "C" fn(*mut c_void, i64) -> c_int;
+type ReportActualFn = unsafe extern "C" fn(*mut c_void, i64) -> c_int;
+type CloseSessionFn = unsafe extern "C" fn(*mut c_void);
+
+/// The target work duration requested by the stream, zero when the hints are disabled
+#[derive(Default)]
+pub(crate) struct PerformanceHintTarget {
+    nanos: AtomicI64,
+}
+
+impl PerformanceHintTarget {
+    pub(crate) fn set(&self, nanos: i64) {
+        self.nanos.store(nanos.max(0), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> i64 {
+        self.nanos.load(Ordering::Relaxed)
+    }
+}
+
+/// The functions of the native performance hint API
+///
+/// The library is loaded once per process and stays loaded, so the symbols are valid forever.
+pub(crate) struct HintApi {
+    manager: *mut c_void,
+    create_session: CreateSessionFn,
+    update_target: UpdateTargetFn,
+    report_actual: ReportActualFn,
+    close: CloseSessionFn,
+}
+
+// The manager is a process-wide singleton which is safe to use from any thread
+unsafe impl Send for HintApi {}
+unsafe impl Sync for HintApi {}
+
+static HINT_API: OnceLock<Option<HintApi>> = OnceLock::new();
+
+impl HintApi {
+    /// Load the API on the current thread, so the callbacks do not call the dynamic loader
+    pub(crate) fn load() {
+        HINT_API.get_or_init(|| unsafe { Self::resolve() });
+    }
+
+    /// Get the loaded API, fails when it was not loaded or is not available
+    fn get() -> Option<&'static Self> {
+        HINT_API.get()?.as_ref()
+    }
+
+    unsafe fn resolve() -> Option<Self> {
+        let library = libc::dlopen(cstr(b"libandroid.so\0"), libc::RTLD_NOW);
+        if library.is_null() {
+            return None;
+        }
+
+        let api = Self::resolve_symbols(library);
+        if api.is_none() {
+            libc::dlclose(library);
+        }
+        api
+    }
+
+    unsafe fn resolve_symbols(library: *mut c_void) -> Option<Self> {
+        let get_manager: GetManagerFn =
+            std::mem::transmute(symbol(library, b"APerformanceHint_getManager\0")?);
+
+        let manager = get_manager();
+        if manager.is_null() {
+            return None;
+        }
+
+        let create_session: CreateSessionFn =
+            std::mem::transmute(symbol(library, b"APerformanceHint_createSession\0")?);
+        let update_target: UpdateTargetFn = std::mem::transmute(symbol(
+            library,
+            b"APerformanceHint_updateTargetWorkDuration\0",
+        )?);
+        let report_actual: ReportActualFn = std::mem::transmute(symbol(
+            library,
+            b"APerformanceHint_reportActualWorkDuration\0",
+        )?);
+        let close: CloseSessionFn =
+            std::mem::transmute(symbol(library, b"APerformanceHint_closeSession\0")?);
+
+        Some(Self {
+            manager,
+            create_session,
+            update_target,
+            report_actual,
+            close,
+        })
+    }
+}
+
+/// The performance hint session of the callback thread
+struct HintSession {
+    api: &'static HintApi,
+    session: *mut c_void,
+    target: i64,
+}
+
+// The session is used from the callback thread only, but closed by the owner of stream
+unsafe impl Send for HintSession {}
+
+impl HintSession {
+    /// Create the session for the current thread, fails when the API is not available
+    fn create(target: i64) -> Option<Self> {
+        let api = HintApi::get()?;
+
+        let thread_id = unsafe { libc::gettid() };
+        let session = unsafe { (api.create_session)(api.manager, &thread_id, 1, target) };
+        if session.is_null() {
+            return None;
+        }
+
+        Some(Self {
+            api,
+            session,
+            target,
+        })
+    }
+}
+
+impl Drop for HintSession {
+    fn drop(&mut self) {
+        unsafe { (self.api.close)(self.session) }
+    }
+}
+
+/// The name must be nul-terminated
+fn cstr(bytes: &'static [u8]) -> *const libc::c_char {
+    bytes.as_ptr() as *const libc::c_char
+}
+
+unsafe fn symbol(library: *mut c_void, name: &'static [u8]) -> Option<*mut c_void> {
+    let symbol = libc::dlsym(library, cstr(name));
+    if symbol.is_null() {
+        None
+    } else {
+        Some(symbol)
+    }
+}
+
+/// The reporter of work duration which lives in the callback context
+#[derive(Default)]
+pub(crate) struct PerformanceHintReporter {
+    session: Option<HintSession>,
+    unsupported: bool,
+}
+
+impl PerformanceHintReporter {
+    /// Start measuring the work, returns the start time when the hints are enabled
+    pub(crate) fn begin(&mut self, target: &PerformanceHintTarget) -> Option<i64> {
+        let target = target.get();
+        if target <= 0 || self.unsupported {
+            return None;
+        }
+
+        match &mut self.session {
+            Some(session) => {
+                if session.target != target {
+                    unsafe { (session.api.update_target)(session.session, target) };
+                    session.target = target;
+                }
+            }
+            None => {
+                // The session is created once on the first callback, because it
+                // needs the id of callback thread
+                self.session = HintSession::create(target);
+                if self.session.is_none() {
+                    self.unsupported = true;
+                    return None;
+                }
+            }
+        }
+
+        Some(clock_nanos(CLOCK_MONOTONIC))
+    }
+
+    /// Report the duration of work started at `start`
+    pub(crate) fn end(&mut self, start: Option<i64>) {
+        if let (Some(start), Some(session)) = (start, &self.session) {
+            let duration = clock_nanos(CLOCK_MONOTONIC) - start;
+            unsafe { (session.api.report_actual)(session.session, duration.max(1)) };
+        }
+    }
+}
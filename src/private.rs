@@ -15,3 +15,111 @@ pub trait RawAudioInputStream {}
 
 /// The raw marker for output stream
 pub trait RawAudioOutputStream {}
+
+/// The raw marker for sample types with plain memory layout
+pub trait RawSample {}
+
+/// The raw marker for frame types with plain memory layout
+pub trait RawFrame {}
+
+/// Call the Oboe function
+///
+/// With `trace-ffi` feature the name, the arguments and the result of call are logged.
+macro_rules! ffi_call {
+    ($func:ident($($arg:expr),* $(,)?)) => {
+        ffi_call!(@bind $func [] [$($arg),*] [a0 a1 a2 a3 a4 a5 a6 a7])
+    };
+    (@bind $func:ident [$($name:ident = $value:expr),*] [$arg:expr $(, $rest:expr)*] [$next:ident $($names:ident)*]) => {
+        ffi_call!(@bind $func [$($name = $value,)* $next = $arg] [$($rest),*] [$($names)*])
+    };
+    (@bind $func:ident [$($name:ident = $value:expr),*] [] [$($names:ident)*]) => {{
+        $(let $name = $value;)*
+        #[cfg(feature = "trace-ffi")]
+        log::trace!(
+            "{}({})",
+            stringify!($func),
+            $crate::FfiArgs(&[$(&$name as &dyn $crate::FfiArg),*])
+        );
+        let result = oboe_sys::$func($($name),*);
+        #[cfg(feature = "trace-ffi")]
+        log::trace!("{} -> {:?}", stringify!($func), result);
+        result
+    }};
+}
+
+pub(crate) use ffi_call;
+
+/// The argument of traced Oboe function
+#[cfg(feature = "trace-ffi")]
+pub(crate) trait FfiArg {
+    fn fmt_arg(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+}
+
+#[cfg(feature = "trace-ffi")]
+macro_rules! ffi_arg_debug {
+    ($($type:ty),*) => {
+        $(
+            impl FfiArg for $type {
+                fn fmt_arg(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    std::fmt::Debug::fmt(self, f)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "trace-ffi")]
+ffi_arg_debug!(bool, i32, i64);
+
+#[cfg(feature = "trace-ffi")]
+impl<T> FfiArg for *const T {
+    fn fmt_arg(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Pointer::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "trace-ffi")]
+impl<T> FfiArg for *mut T {
+    fn fmt_arg(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Pointer::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "trace-ffi")]
+impl<T> FfiArg for &T {
+    fn fmt_arg(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Pointer::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "trace-ffi")]
+impl<T> FfiArg for &mut T {
+    fn fmt_arg(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Pointer::fmt(self, f)
+    }
+}
+
+/// The handlers are passed as optional function pointers
+#[cfg(feature = "trace-ffi")]
+impl<T> FfiArg for Option<T> {
+    fn fmt_arg(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.is_some() { "Some(..)" } else { "None" })
+    }
+}
+
+/// The arguments of traced Oboe function
+#[cfg(feature = "trace-ffi")]
+pub(crate) struct FfiArgs<'a>(pub &'a [&'a dyn FfiArg]);
+
+#[cfg(feature = "trace-ffi")]
+impl<'a> std::fmt::Display for FfiArgs<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, arg) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            arg.fmt_arg(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use super::{IsFrame, IsSample};
+
+/**
+ * The quality of resampling
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResamplerQuality {
+    /**
+     * Linear interpolation
+     *
+     * It is cheap and real-time safe, but attenuates high frequencies and may produce aliasing.
+     */
+    #[default]
+    Linear,
+
+    /**
+     * Band-limited sinc interpolation using `rubato`
+     *
+     * It allocates while processing, so it is better to use it outside of the data callback.
+     */
+    #[cfg(feature = "rubato")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rubato")))]
+    Sinc,
+}
+
+/**
+ * The streaming sample rate converter
+ *
+ * The state is kept between calls, so the input can be split into chunks of any size, e.g.
+ * callback buffers. Use it with
+ * [`AudioStreamBuilder::set_sample_rate_conversion_quality`](crate::AudioStreamBuilder::set_sample_rate_conversion_quality)
+ * set to [`SampleRateConversionQuality::None`](crate::SampleRateConversionQuality::None)
+ * to control resampling quality yourself.
+ */
+pub struct Resampler<F: IsFrame> {
+    input_rate: i32,
+    output_rate: i32,
+    backend: Backend<F>,
+}
+
+enum Backend<F> {
+    Linear(LinearResampler<F>),
+    #[cfg(feature = "rubato")]
+    Sinc(SincResampler),
+}
+
+impl<F: IsFrame> Resampler<F> {
+    /**
+     * Create resampler with default quality
+     *
+     * Panics when any of sample rates is not positive.
+     */
+    pub fn new(input_rate: i32, output_rate: i32) -> Self {
+        Self::with_quality(input_rate, output_rate, ResamplerQuality::default())
+    }
+
+    /**
+     * Create resampler with specified quality
+     *
+     * Panics when any of sample rates is not positive.
+     */
+    pub fn with_quality(input_rate: i32, output_rate: i32, quality: ResamplerQuality) -> Self {
+        assert!(
+            input_rate > 0 && output_rate > 0,
+            "Sample rates must be positive"
+        );
+
+        let ratio = input_rate as f64 / output_rate as f64;
+        let backend = match quality {
+            ResamplerQuality::Linear => Backend::Linear(LinearResampler::new(ratio)),
+            #[cfg(feature = "rubato")]
+            ResamplerQuality::Sinc => Backend::Sinc(SincResampler::new(ratio, F::CHANNELS)),
+        };
+
+        Self {
+            input_rate,
+            output_rate,
+            backend,
+        }
+    }
+
+    /**
+     * Get the sample rate of input frames
+     */
+    pub fn get_input_rate(&self) -> i32 {
+        self.input_rate
+    }
+
+    /**
+     * Get the sample rate of output frames
+     */
+    pub fn get_output_rate(&self) -> i32 {
+        self.output_rate
+    }
+
+    /**
+     * Resample the input frames and append the result to the output
+     *
+     * The number of output frames may vary between calls, because some frames are kept
+     * for interpolation. Reserve capacity of output in advance to avoid allocations in
+     * the data callback.
+     */
+    pub fn process(&mut self, input: &[F], output: &mut Vec<F>) {
+        match &mut self.backend {
+            Backend::Linear(resampler) => resampler.process(input, output),
+            #[cfg(feature = "rubato")]
+            Backend::Sinc(resampler) => resampler.process(input, output),
+        }
+    }
+
+    /**
+     * Reset the state of resampler
+     */
+    pub fn reset(&mut self) {
+        let ratio = self.input_rate as f64 / self.output_rate as f64;
+        match &mut self.backend {
+            Backend::Linear(resampler) => *resampler = LinearResampler::new(ratio),
+            #[cfg(feature = "rubato")]
+            Backend::Sinc(resampler) => *resampler = SincResampler::new(ratio, F::CHANNELS),
+        }
+    }
+}
+
+struct LinearResampler<F> {
+    step: f64,
+    phase: f64,
+    last: Option<F>,
+}
+
+impl<F: IsFrame> LinearResampler<F> {
+    fn new(step: f64) -> Self {
+        Self {
+            step,
+            phase: 0.0,
+            last: None,
+        }
+    }
+
+    fn process(&mut self, input: &[F], output: &mut Vec<F>) {
+        // The sequence of frames is the last frame of previous chunk followed by input
+        let last = self.last;
+        let offset = last.is_some() as usize;
+        let len = input.len() + offset;
+        let frame = |index: usize| match (index, last) {
+            (0, Some(last)) => last,
+            _ => input[index - offset],
+        };
+
+        while self.phase + 1.0 < len as f64 {
+            let index = self.phase as usize;
+            let fraction = (self.phase - index as f64) as f32;
+            let (current, next) = (frame(index), frame(index + 1));
+
+            let mut result = F::default();
+            for channel in 0..F::CHANNELS {
+                let current = current.channel(channel).to_f32();
+                let next = next.channel(channel).to_f32();
+                result.set_channel(
+                    channel,
+                    F::Sample::from_f32(current + (next - current) * fraction),
+                );
+            }
+            output.push(result);
+
+            self.phase += self.step;
+        }
+
+        if len > 0 {
+            self.phase -= (len - 1) as f64;
+            self.last = Some(frame(len - 1));
+        }
+    }
+}
+
+#[cfg(feature = "rubato")]
+struct SincResampler {
+    resampler: rubato::SincFixedIn<f32>,
+    pending: Vec<Vec<f32>>,
+}
+
+#[cfg(feature = "rubato")]
+impl SincResampler {
+    const CHUNK_SIZE: usize = 256;
+
+    fn new(ratio: f64, channels: usize) -> Self {
+        use rubato::{InterpolationParameters, InterpolationType, WindowFunction};
+
+        let parameters = InterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: InterpolationType::Linear,
+            oversampling_factor: 256,
+            window: WindowFunction::BlackmanHarris2,
+        };
+
+        Self {
+            // rubato uses the ratio of output rate to input rate
+            resampler: rubato::SincFixedIn::new(
+                1.0 / ratio,
+                1.0,
+                parameters,
+                Self::CHUNK_SIZE,
+                channels,
+            )
+            .expect("Invalid resampler parameters"),
+            pending: vec![Vec::with_capacity(Self::CHUNK_SIZE); channels],
+        }
+    }
+
+    fn process<F: IsFrame>(&mut self, input: &[F], output: &mut Vec<F>) {
+        use rubato::Resampler;
+
+        for frame in input {
+            for (channel, pending) in self.pending.iter_mut().enumerate() {
+                pending.push(frame.channel(channel).to_f32());
+            }
+
+            if self.pending[0].len() >= self.resampler.input_frames_next() {
+                if let Ok(resampled) = self.resampler.process(&self.pending, None) {
+                    for index in 0..resampled[0].len() {
+                        let mut result = F::default();
+                        for (channel, samples) in resampled.iter().enumerate() {
+                            result.set_channel(channel, F::Sample::from_f32(samples[index]));
+                        }
+                        output.push(result);
+                    }
+                }
+                for pending in &mut self.pending {
+                    pending.clear();
+                }
+            }
+        }
+    }
+}
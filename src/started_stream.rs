@@ -0,0 +1,76 @@
+use std::ops::{Deref, DerefMut};
+
+use super::{AudioStream, Result};
+
+/**
+ * The guard of started audio stream
+ *
+ * The stream is started by [`AudioStream::started`] and stopped asynchronously when the guard
+ * is dropped, so it is impossible to forget to stop the stream, which is especially important
+ * for exclusive streams.
+ *
+ * The guard dereferences to the underlying stream, so it can be queried as usual.
+ */
+pub struct StartedStream<S: AudioStream> {
+    stream: S,
+}
+
+impl<S: AudioStream> StartedStream<S> {
+    pub(crate) fn new(mut stream: S) -> Result<Self> {
+        if let Err(error) = stream.request_start() {
+            // The stream which failed to start is dropped, so close it without the warning
+            // about the stream which was never started
+            let _ = stream.close();
+            return Err(error);
+        }
+        Ok(Self { stream })
+    }
+}
+
+impl<S: AudioStream> Deref for StartedStream<S> {
+    type Target = S;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stream
+    }
+}
+
+impl<S: AudioStream> DerefMut for StartedStream<S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stream
+    }
+}
+
+impl<S: AudioStream> Drop for StartedStream<S> {
+    /**
+     * Request stopping the stream
+     *
+     * The stop errors are ignored, but logged with `log` feature.
+     */
+    fn drop(&mut self) {
+        let _result = self.stream.request_stop();
+
+        #[cfg(feature = "log")]
+        if let Err(error) = _result {
+            log::warn!("Unable to stop audio stream: {}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::{test_stream::TestStream, Error};
+
+    #[test]
+    fn closes_stream_which_failed_to_start() {
+        let mut stream = TestStream::new();
+        stream.start_error = Some(Error::InvalidState);
+        let closed = stream.closed.clone();
+
+        assert_eq!(stream.started().err(), Some(Error::InvalidState));
+        assert_eq!(closed.load(Ordering::SeqCst), 1);
+    }
+}
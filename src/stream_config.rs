@@ -0,0 +1,127 @@
+use super::{
+    AudioApi, AudioFormat, AudioStreamBase, ChannelCount, ContentType, InputPreset,
+    PerformanceMode, SampleRateConversionQuality, SessionId, SharingMode, Usage,
+};
+
+/**
+ * The settings of builder which was used to open a stream
+ *
+ * It is captured on opening the asynchronous stream, so the same stream can be reopened,
+ * see [`AudioStreamAsync::reconnect`](crate::AudioStreamAsync::reconnect).
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamConfig {
+    /**
+     * The requested audio API
+     */
+    pub audio_api: AudioApi,
+
+    /**
+     * The requested sample rate
+     */
+    pub sample_rate: i32,
+
+    /**
+     * The number of channels
+     */
+    pub channel_count: ChannelCount,
+
+    /**
+     * The audio sample format
+     */
+    pub format: AudioFormat,
+
+    /**
+     * The requested number of frames for the data callback
+     */
+    pub frames_per_callback: i32,
+
+    /**
+     * The requested buffer capacity in frames
+     */
+    pub buffer_capacity_in_frames: i32,
+
+    /**
+     * The requested sharing mode
+     */
+    pub sharing_mode: SharingMode,
+
+    /**
+     * The requested performance mode
+     */
+    pub performance_mode: PerformanceMode,
+
+    /**
+     * The usage
+     */
+    pub usage: Usage,
+
+    /**
+     * The content type
+     */
+    pub content_type: ContentType,
+
+    /**
+     * The input preset
+     */
+    pub input_preset: InputPreset,
+
+    /**
+     * The session ID allocation strategy
+     */
+    pub session_id: SessionId,
+
+    /**
+     * The requested device ID
+     */
+    pub device_id: i32,
+
+    /**
+     * Allow channel count conversion
+     */
+    pub channel_conversion_allowed: bool,
+
+    /**
+     * Allow format conversion
+     */
+    pub format_conversion_allowed: bool,
+
+    /**
+     * The quality of sample rate conversion
+     */
+    pub sample_rate_conversion_quality: SampleRateConversionQuality,
+}
+
+impl StreamConfig {
+    pub(crate) fn new<T: AudioStreamBase>(base: &T, audio_api: AudioApi) -> Self {
+        Self {
+            audio_api,
+            sample_rate: base.get_sample_rate(),
+            channel_count: base.get_channel_count(),
+            format: base.get_format(),
+            frames_per_callback: base.get_frames_per_callback(),
+            buffer_capacity_in_frames: base.get_buffer_capacity_in_frames(),
+            sharing_mode: base.get_sharing_mode(),
+            performance_mode: base.get_performance_mode(),
+            usage: base.get_usage(),
+            content_type: base.get_content_type(),
+            input_preset: base.get_input_preset(),
+            session_id: base.get_session_id(),
+            device_id: base.get_device_id(),
+            channel_conversion_allowed: base.is_channel_conversion_allowed(),
+            format_conversion_allowed: base.is_format_conversion_allowed(),
+            sample_rate_conversion_quality: base.get_sample_rate_conversion_quality(),
+        }
+    }
+
+    /**
+     * Get the config which follows the default device with its native sample rate
+     */
+    pub(crate) fn adapted(&self) -> Self {
+        Self {
+            sample_rate: 0,
+            device_id: 0,
+            ..*self
+        }
+    }
+}
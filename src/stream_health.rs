@@ -0,0 +1,71 @@
+use std::fmt::{self, Display};
+
+use super::{AudioApi, AudioStreamSafe, StreamState};
+
+/**
+ * The health summary of stream for logging
+ *
+ * It is displayed as a single line, e.g. `Started AAudio xruns=0 buffer=192/1920 latency=21.3ms`.
+ *
+ * See [`AudioStreamSafe::health`].
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamHealth {
+    /**
+     * The current state
+     */
+    pub state: StreamState,
+
+    /**
+     * The number of xruns, `None` when unsupported
+     */
+    pub xruns: Option<i32>,
+
+    /**
+     * The buffer size in frames
+     */
+    pub buffer_size: i32,
+
+    /**
+     * The buffer capacity in frames
+     */
+    pub capacity: i32,
+
+    /**
+     * The estimated latency in milliseconds, `None` when unavailable
+     */
+    pub latency_ms: Option<f64>,
+
+    /**
+     * The underlying audio API
+     */
+    pub api: AudioApi,
+}
+
+impl StreamHealth {
+    pub(crate) fn new<T: AudioStreamSafe + ?Sized>(stream: &mut T) -> Self {
+        Self {
+            state: stream.get_state(),
+            xruns: stream.get_xrun_count().ok(),
+            buffer_size: stream.get_buffer_size_in_frames(),
+            capacity: stream.get_buffer_capacity_in_frames(),
+            latency_ms: stream.calculate_latency_millis().ok(),
+            api: stream.get_audio_api(),
+        }
+    }
+}
+
+impl Display for StreamHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {:?} xruns=", self.state, self.api)?;
+        match self.xruns {
+            Some(xruns) => write!(f, "{}", xruns)?,
+            None => "n/a".fmt(f)?,
+        }
+        write!(f, " buffer={}/{} latency=", self.buffer_size, self.capacity)?;
+        match self.latency_ms {
+            Some(latency) => write!(f, "{:.1}ms", latency),
+            None => "n/a".fmt(f),
+        }
+    }
+}
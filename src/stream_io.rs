@@ -0,0 +1,290 @@
+use std::{io, mem::size_of, thread, time::Duration};
+
+use super::{
+    frames_as_bytes, frames_as_bytes_mut, AudioInputStreamSync, AudioOutputStreamSync,
+    AudioStreamSync, Error, Input, IsFrame, IsFrameType, Output, StreamState, Timeout,
+};
+
+/**
+ * The maximum number of frames transferred to the stream at once
+ */
+const CHUNK_FRAMES: usize = 1024;
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error {
+            Error::Disconnected | Error::Closed => io::ErrorKind::BrokenPipe,
+            Error::Timeout => io::ErrorKind::TimedOut,
+            Error::WouldBlock => io::ErrorKind::WouldBlock,
+            Error::IllegalArgument | Error::InvalidFormat | Error::InvalidRate => {
+                io::ErrorKind::InvalidInput
+            }
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// The error of the transfer which ran out of time before any frame was transferred
+fn no_progress(timeout: Timeout) -> io::Error {
+    match timeout {
+        Timeout::None => io::ErrorKind::WouldBlock.into(),
+        _ => io::ErrorKind::TimedOut.into(),
+    }
+}
+
+/**
+ * The input stream adapter which implements [`std::io::Read`]
+ *
+ * The bytes are the raw interleaved PCM frames in the native byte order, so the stream can be
+ * copied to a socket or a file using [`std::io::copy`]. When the buffer passed to `read` does
+ * not hold a whole number of frames, the rest of the last frame is kept for the next `read`.
+ *
+ * The reading blocks according to the [`Timeout`], which is [`Timeout::Forever`] by default.
+ * When no frames arrive in time the [`io::ErrorKind::TimedOut`] error is returned, or
+ * [`io::ErrorKind::WouldBlock`] for [`Timeout::None`], because reading zero bytes would mean
+ * the end of stream. The disconnection is returned as [`io::ErrorKind::BrokenPipe`].
+ *
+ * The stream must be started before reading.
+ */
+pub struct StreamReader<S: AudioInputStreamSync> {
+    stream: S,
+    timeout: Timeout,
+    frames: Vec<<S::FrameType as IsFrameType>::Type>,
+    start: usize,
+    end: usize,
+}
+
+impl<S> StreamReader<S>
+where
+    S: AudioInputStreamSync,
+    <S::FrameType as IsFrameType>::Type: IsFrame,
+{
+    /**
+     * Wrap the input stream to read it as bytes
+     */
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            timeout: Timeout::Forever,
+            frames: vec![Default::default(); CHUNK_FRAMES],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /**
+     * Set the timeout of each read
+     */
+    pub fn set_timeout(&mut self, timeout: Timeout) {
+        self.timeout = timeout;
+    }
+
+    /**
+     * Get the underlying stream
+     */
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /**
+     * Unwrap the underlying stream, the bytes of partially read frame are lost
+     */
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> io::Read for StreamReader<S>
+where
+    S: AudioInputStreamSync,
+    <S::FrameType as IsFrameType>::Type: IsFrame,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.start == self.end {
+            let frame_size = size_of::<<S::FrameType as IsFrameType>::Type>();
+            let wanted = buf.len().div_ceil(frame_size).min(self.frames.len());
+
+            let frames = self
+                .stream
+                .read(&mut self.frames[..wanted], self.timeout)?
+                .max(0) as usize;
+            if frames == 0 {
+                return Err(no_progress(self.timeout));
+            }
+
+            self.start = 0;
+            self.end = frames * frame_size;
+        }
+
+        let pending = &frames_as_bytes(&self.frames)[self.start..self.end];
+        let count = pending.len().min(buf.len());
+        buf[..count].copy_from_slice(&pending[..count]);
+        self.start += count;
+        Ok(count)
+    }
+}
+
+/**
+ * The output stream adapter which implements [`std::io::Write`]
+ *
+ * The bytes are the raw interleaved PCM frames in the native byte order, so a socket or a file
+ * can be copied to the stream using [`std::io::copy`]. The trailing bytes of incomplete frame
+ * are buffered until the rest of the frame is written.
+ *
+ * The writing blocks according to the [`Timeout`], which is [`Timeout::Forever`] by default.
+ * When no frames fit in time the [`io::ErrorKind::TimedOut`] error is returned, or
+ * [`io::ErrorKind::WouldBlock`] for [`Timeout::None`]. The disconnection is returned
+ * as [`io::ErrorKind::BrokenPipe`].
+ *
+ * The [`flush`](std::io::Write::flush) blocks until the written frames are played, i.e.
+ * the buffer of stream is drained, unless the stream is not started.
+ */
+pub struct StreamWriter<S: AudioOutputStreamSync> {
+    stream: S,
+    timeout: Timeout,
+    frames: Vec<<S::FrameType as IsFrameType>::Type>,
+    partial: Vec<u8>,
+}
+
+impl<S> StreamWriter<S>
+where
+    S: AudioOutputStreamSync,
+    <S::FrameType as IsFrameType>::Type: IsFrame,
+{
+    /**
+     * Wrap the output stream to write it as bytes
+     */
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            timeout: Timeout::Forever,
+            frames: vec![Default::default(); CHUNK_FRAMES],
+            partial: Vec::new(),
+        }
+    }
+
+    /**
+     * Set the timeout of each write
+     */
+    pub fn set_timeout(&mut self, timeout: Timeout) {
+        self.timeout = timeout;
+    }
+
+    /**
+     * Get the underlying stream
+     */
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /**
+     * Unwrap the underlying stream, the bytes of incomplete frame are lost
+     */
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    fn frame_size() -> usize {
+        size_of::<<S::FrameType as IsFrameType>::Type>()
+    }
+
+    /// Write the frames from the start of chunk, returns the number of frames written
+    fn write_chunk(&mut self, frames: usize) -> io::Result<usize> {
+        let written = self
+            .stream
+            .write(&self.frames[..frames], self.timeout)?
+            .max(0) as usize;
+        if written == 0 {
+            Err(no_progress(self.timeout))
+        } else {
+            Ok(written)
+        }
+    }
+
+    /// Write the buffered frame when it is complete
+    fn write_partial(&mut self) -> io::Result<()> {
+        if self.partial.len() == Self::frame_size() {
+            frames_as_bytes_mut(&mut self.frames[..1]).copy_from_slice(&self.partial);
+            self.write_chunk(1)?;
+            self.partial.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<S> io::Write for StreamWriter<S>
+where
+    S: AudioOutputStreamSync,
+    <S::FrameType as IsFrameType>::Type: IsFrame,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let frame_size = Self::frame_size();
+
+        // The complete frame which did not fit last time goes first
+        self.write_partial()?;
+
+        if !self.partial.is_empty() {
+            let count = (frame_size - self.partial.len()).min(buf.len());
+            self.partial.extend_from_slice(&buf[..count]);
+            // The frame is buffered, so the error is reported by the next write
+            let _ = self.write_partial();
+            return Ok(count);
+        }
+
+        let frames = (buf.len() / frame_size).min(self.frames.len());
+        if frames == 0 {
+            self.partial.extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+
+        let bytes = frames * frame_size;
+        frames_as_bytes_mut(&mut self.frames[..frames]).copy_from_slice(&buf[..bytes]);
+        Ok(self.write_chunk(frames)? * frame_size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_partial()?;
+
+        while self.stream.get_state() == StreamState::Started {
+            // The queued frames cannot be queried on some OpenSL ES devices
+            let queued = match self.stream.get_available_frames() {
+                Ok(queued) if queued > 0 => queued,
+                _ => break,
+            };
+            let burst = self.stream.get_frames_per_burst().max(1);
+            let wait = self.stream.frames_to_duration(queued.min(burst) as i64);
+            thread::sleep(wait.max(Duration::from_millis(1)));
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: IsFrameType> AudioStreamSync<Input, F>
+where
+    F::Type: IsFrame,
+{
+    /**
+     * Convert the stream into [`std::io::Read`], see [`StreamReader`]
+     */
+    pub fn into_reader(self) -> StreamReader<Self> {
+        StreamReader::new(self)
+    }
+}
+
+impl<F: IsFrameType> AudioStreamSync<Output, F>
+where
+    F::Type: IsFrame,
+{
+    /**
+     * Convert the stream into [`std::io::Write`], see [`StreamWriter`]
+     */
+    pub fn into_writer(self) -> StreamWriter<Self> {
+        StreamWriter::new(self)
+    }
+}
@@ -0,0 +1,49 @@
+use super::{AudioFormat, AudioStreamSafe, ChannelCount};
+
+/**
+ * The snapshot of stream parameters
+ *
+ * It is plain data, so it can be shared with other threads, e.g. in an `Arc`,
+ * without touching the live stream.
+ *
+ * See [`AudioStreamSafe::metadata`].
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamMetadata {
+    /**
+     * The sample rate
+     */
+    pub sample_rate: i32,
+
+    /**
+     * The number of channels
+     */
+    pub channels: ChannelCount,
+
+    /**
+     * The audio sample format
+     */
+    pub format: AudioFormat,
+
+    /**
+     * The number of frames read or written by the endpoint at one time
+     */
+    pub frames_per_burst: i32,
+
+    /**
+     * The buffer capacity in frames
+     */
+    pub buffer_capacity: i32,
+}
+
+impl StreamMetadata {
+    pub(crate) fn new<T: AudioStreamSafe + ?Sized>(stream: &mut T) -> Self {
+        Self {
+            sample_rate: stream.get_sample_rate(),
+            channels: stream.get_channel_count(),
+            format: stream.get_format(),
+            frames_per_burst: stream.get_frames_per_burst(),
+            buffer_capacity: stream.get_buffer_capacity_in_frames(),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::{
+    AudioApi, AudioFormat, AudioInputStream, AudioInputStreamSafe, AudioInputStreamSync,
+    AudioStream, AudioStreamBase, AudioStreamSafe, ChannelCount, ClockId, ContentType, Direction,
+    Error, FrameTimestamp, InputPreset, Mono, PerformanceMode, Result, SampleRateConversionQuality,
+    SessionId, SharingMode, Status, StreamState, Timeout, Usage,
+};
+
+/// The emulated mono input stream for unit tests
+///
+/// The reads return the frames pushed to `frames`, the closings are counted, so they can be
+/// checked after the stream was dropped. The waiting for state change fails with `wait_error`.
+pub(crate) struct TestStream {
+    pub(crate) frames: VecDeque<f32>,
+    pub(crate) capacity: i32,
+    pub(crate) sample_rate: i32,
+    pub(crate) state: StreamState,
+    pub(crate) start_error: Option<Error>,
+    pub(crate) wait_error: Option<Error>,
+    pub(crate) closed: Arc<AtomicUsize>,
+}
+
+impl TestStream {
+    pub(crate) fn new() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            capacity: 192,
+            sample_rate: 48000,
+            state: StreamState::Open,
+            start_error: None,
+            wait_error: None,
+            closed: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl AudioStreamBase for TestStream {
+    fn get_channel_count(&self) -> ChannelCount {
+        ChannelCount::Mono
+    }
+
+    fn get_direction(&self) -> Direction {
+        Direction::Input
+    }
+
+    fn get_sample_rate(&self) -> i32 {
+        self.sample_rate
+    }
+
+    fn get_frames_per_callback(&self) -> i32 {
+        0
+    }
+
+    fn get_format(&self) -> AudioFormat {
+        AudioFormat::F32
+    }
+
+    fn get_buffer_size_in_frames(&self) -> i32 {
+        self.capacity
+    }
+
+    fn get_buffer_capacity_in_frames(&self) -> i32 {
+        self.capacity
+    }
+
+    fn get_sharing_mode(&self) -> SharingMode {
+        SharingMode::Shared
+    }
+
+    fn get_performance_mode(&self) -> PerformanceMode {
+        PerformanceMode::LowLatency
+    }
+
+    fn get_device_id(&self) -> i32 {
+        0
+    }
+
+    fn get_usage(&self) -> Usage {
+        Usage::Media
+    }
+
+    fn get_content_type(&self) -> ContentType {
+        ContentType::Music
+    }
+
+    fn get_input_preset(&self) -> InputPreset {
+        InputPreset::VoicePerformance
+    }
+
+    fn get_session_id(&self) -> SessionId {
+        SessionId::None
+    }
+
+    fn get_allocated_session_id(&self) -> Option<i32> {
+        None
+    }
+
+    fn is_channel_conversion_allowed(&self) -> bool {
+        false
+    }
+
+    fn is_format_conversion_allowed(&self) -> bool {
+        false
+    }
+
+    fn get_sample_rate_conversion_quality(&self) -> SampleRateConversionQuality {
+        SampleRateConversionQuality::None
+    }
+}
+
+impl AudioStreamSafe for TestStream {
+    fn get_state(&self) -> StreamState {
+        self.state
+    }
+
+    fn set_buffer_size_in_frames(&mut self, _requested_frames: i32) -> Result<i32> {
+        Err(Error::Unimplemented)
+    }
+
+    fn get_xrun_count(&self) -> Result<i32> {
+        Ok(0)
+    }
+
+    fn is_xrun_count_supported(&self) -> bool {
+        false
+    }
+
+    fn get_frames_per_burst(&mut self) -> i32 {
+        self.capacity
+    }
+
+    fn get_bytes_per_sample(&mut self) -> i32 {
+        4
+    }
+
+    fn calculate_latency_millis(&mut self) -> Result<f64> {
+        Ok(0.0)
+    }
+
+    fn get_timestamp(&mut self, _clock_id: ClockId) -> Result<FrameTimestamp> {
+        Err(Error::Unimplemented)
+    }
+
+    fn get_audio_api(&self) -> AudioApi {
+        AudioApi::Unspecified
+    }
+
+    fn get_available_frames(&mut self) -> Result<i32> {
+        Ok(self.frames.len() as i32)
+    }
+}
+
+impl AudioStream for TestStream {
+    fn close(&mut self) -> Status {
+        self.closed.fetch_add(1, Ordering::SeqCst);
+        self.state = StreamState::Closed;
+        Ok(())
+    }
+
+    fn start_with_timeout(&mut self, _timeout_nanoseconds: i64) -> Status {
+        self.request_start()
+    }
+
+    fn stop_with_timeout(&mut self, _timeout_nanoseconds: i64) -> Status {
+        self.request_stop()
+    }
+
+    fn request_start(&mut self) -> Status {
+        if let Some(error) = self.start_error {
+            return Err(error);
+        }
+        self.state = StreamState::Started;
+        Ok(())
+    }
+
+    fn request_stop(&mut self) -> Status {
+        self.state = StreamState::Stopped;
+        Ok(())
+    }
+
+    fn wait_for_state_change(
+        &mut self,
+        _input_state: StreamState,
+        _timeout_nanoseconds: i64,
+    ) -> Result<StreamState> {
+        match self.wait_error {
+            Some(error) => Err(error),
+            None => Ok(self.state),
+        }
+    }
+
+    fn wait_for_available_frames(
+        &mut self,
+        _num_frames: i32,
+        _timeout_nanoseconds: i64,
+    ) -> Result<i32> {
+        self.get_available_frames()
+    }
+}
+
+impl AudioInputStreamSafe for TestStream {
+    fn get_frames_read(&mut self) -> i64 {
+        0
+    }
+}
+
+impl AudioInputStream for TestStream {}
+
+impl AudioInputStreamSync for TestStream {
+    type FrameType = (f32, Mono);
+
+    fn read(&mut self, buffer: &mut [f32], _timeout: impl Into<Timeout>) -> Result<i32> {
+        let count = buffer.len().min(self.frames.len());
+        for (frame, value) in buffer.iter_mut().zip(self.frames.drain(..count)) {
+            *frame = value;
+        }
+        Ok(count as i32)
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    mem::{align_of, size_of, size_of_val},
+    slice::{from_raw_parts, from_raw_parts_mut},
+};
+
+use num_traits::NumCast;
+
+use super::{clamp_sample, AudioStreamSafe, IsFrame, IsSample};
+
+/**
+ * Reinterpret the frames as raw bytes without copying
+ *
+ * The samples are in native byte order, which is little-endian on all Android targets.
+ */
+pub fn frames_as_bytes<F: IsFrame>(frames: &[F]) -> &[u8] {
+    unsafe { from_raw_parts(frames.as_ptr() as *const u8, size_of_val(frames)) }
+}
+
+/**
+ * Reinterpret the mutable frames as raw bytes without copying
+ */
+pub fn frames_as_bytes_mut<F: IsFrame>(frames: &mut [F]) -> &mut [u8] {
+    unsafe { from_raw_parts_mut(frames.as_mut_ptr() as *mut u8, size_of_val(frames)) }
+}
+
+/**
+ * Reinterpret the raw bytes as frames without copying
+ *
+ * Returns `None` when the bytes is not properly aligned for the frame type or
+ * the length is not a multiple of the frame size.
+ */
+pub fn bytes_as_frames<F: IsFrame>(bytes: &[u8]) -> Option<&[F]> {
+    if is_frames_layout::<F>(bytes) {
+        Some(unsafe { from_raw_parts(bytes.as_ptr() as *const F, bytes.len() / size_of::<F>()) })
+    } else {
+        None
+    }
+}
+
+/**
+ * Reinterpret the mutable raw bytes as frames without copying
+ *
+ * Returns `None` when the bytes is not properly aligned for the frame type or
+ * the length is not a multiple of the frame size.
+ */
+pub fn bytes_as_frames_mut<F: IsFrame>(bytes: &mut [u8]) -> Option<&mut [F]> {
+    if is_frames_layout::<F>(bytes) {
+        Some(unsafe {
+            from_raw_parts_mut(bytes.as_mut_ptr() as *mut F, bytes.len() / size_of::<F>())
+        })
+    } else {
+        None
+    }
+}
+
+fn is_frames_layout<F>(bytes: &[u8]) -> bool {
+    bytes.as_ptr().align_offset(align_of::<F>()) == 0
+        && bytes.chunks_exact(size_of::<F>()).remainder().is_empty()
+}
+
+/**
+ * Duplicate mono samples to both channels of stereo frames
+ *
+ * Converts the `min(input.len(), output.len())` frames.
+ */
+pub fn mono_to_stereo<T: IsSample>(input: &[T], output: &mut [(T, T)]) {
+    for (sample, frame) in input.iter().zip(output.iter_mut()) {
+        *frame = (*sample, *sample);
+    }
+}
+
+/**
+ * Mix stereo frames to mono samples by averaging the channels
+ *
+ * The sum is computed with wider type, so full-scale integer samples do not overflow.
+ * Converts the `min(input.len(), output.len())` frames.
+ */
+pub fn stereo_to_mono<T: IsSample + NumCast>(input: &[(T, T)], output: &mut [T]) {
+    for (frame, sample) in input.iter().zip(output.iter_mut()) {
+        *sample = average(&[frame.0, frame.1]);
+    }
+}
+
+/**
+ * Mix interleaved samples with specified number of channels to mono samples by averaging
+ *
+ * The sum is computed with wider type, so full-scale integer samples do not overflow.
+ * Converts the `min(input.len() / channels, output.len())` frames.
+ */
+pub fn downmix_to_mono<T: IsSample + NumCast>(input: &[T], channels: usize, output: &mut [T]) {
+    if channels == 0 {
+        return;
+    }
+    for (frame, sample) in input.chunks_exact(channels).zip(output.iter_mut()) {
+        *sample = average(frame);
+    }
+}
+
+fn average<T: IsSample + NumCast>(samples: &[T]) -> T {
+    let sum: f64 = samples
+        .iter()
+        .map(|sample| sample.to_f64().unwrap_or_default())
+        .sum();
+    clamp_sample(sum / samples.len() as f64)
+}
+
+/**
+ * The scratch buffers of samples for processing input and output streams
+ *
+ * The buffers are preallocated using [`AudioStreamSafe::recommended_buffer_len`],
+ * so they can be used in the callbacks without allocation.
+ *
+ * The burst may differ when the streams are reopened, so the buffers must be recreated.
+ */
+#[derive(Debug, Clone)]
+pub struct StreamBuffers<T> {
+    /**
+     * The buffer for the input stream samples
+     */
+    pub input: Vec<T>,
+
+    /**
+     * The buffer for the output stream samples
+     */
+    pub output: Vec<T>,
+}
+
+impl<T: Copy + Default> StreamBuffers<T> {
+    /**
+     * Allocate the buffers of `multiplier` bursts of each stream
+     */
+    pub fn new<I, O>(input: &mut I, output: &mut O, multiplier: usize) -> Self
+    where
+        I: AudioStreamSafe + ?Sized,
+        O: AudioStreamSafe + ?Sized,
+    {
+        Self {
+            input: vec![T::default(); input.recommended_buffer_len(multiplier)],
+            output: vec![T::default(); output.recommended_buffer_len(multiplier)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_SCALE: [(i16, i16); 4] = [
+        (i16::MAX, i16::MAX),
+        (i16::MIN, i16::MIN),
+        (i16::MIN, i16::MAX),
+        (i16::MAX, i16::MIN),
+    ];
+
+    #[test]
+    fn stereo_to_mono_full_scale() {
+        let mut output = [1; 4];
+        stereo_to_mono(&FULL_SCALE, &mut output);
+        assert_eq!(output, [i16::MAX, i16::MIN, 0, 0]);
+    }
+
+    #[test]
+    fn downmix_to_mono_full_scale() {
+        let input: Vec<i16> = FULL_SCALE
+            .iter()
+            .flat_map(|&(left, right)| [left, right])
+            .collect();
+        let mut output = [1; 4];
+        downmix_to_mono(&input, 2, &mut output);
+        assert_eq!(output, [i16::MAX, i16::MIN, 0, 0]);
+    }
+}